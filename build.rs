@@ -0,0 +1,14 @@
+use std::env;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rustc-check-cfg=cfg(renderdoc_enabled)");
+
+    // RenderDoc is only loaded in debug builds, unless the `release` feature asks for it.
+    let capture = env::var_os("CARGO_FEATURE_CAPTURE").is_some();
+    let release = env::var_os("CARGO_FEATURE_RELEASE").is_some();
+    let debug_assertions = env::var_os("CARGO_CFG_DEBUG_ASSERTIONS").is_some();
+    if capture && (debug_assertions || release) {
+        println!("cargo:rustc-cfg=renderdoc_enabled");
+    }
+}
@@ -1,10 +1,23 @@
 use bevy::prelude::*;
 use bevy_renderdoc::*;
 
-fn trigger_capture(rd: Option<NonSendMut<RenderDocResource>>) {
-    if let Some(mut rd) = rd {
-        rd.trigger_capture();
-        rd.launch_replay_ui(true, None).unwrap();
+fn trigger_capture(mut requests: EventWriter<CaptureRequest>) {
+    requests.send(CaptureRequest {
+        title: Some("Startup".into()),
+        open_ui: true,
+        ..default()
+    });
+}
+
+fn capture_on_space(keys: Res<Input<KeyCode>>, mut commands: Commands) {
+    if keys.just_pressed(KeyCode::Space) {
+        commands.capture_frames(3);
+    }
+}
+
+fn log_capture(mut completed: EventReader<CaptureCompleted>) {
+    for capture in completed.iter() {
+        info!("Captured frame {} to {}", capture.frame, capture.path.display());
     }
 }
 
@@ -12,6 +25,9 @@ fn main() {
     App::new()
         .add_plugin(RenderDocPlugin)
         .add_plugins(DefaultPlugins)
+        .add_plugin(RenderDocRenderPlugin)
         .add_startup_system(trigger_capture)
+        .add_system(capture_on_space)
+        .add_system(log_capture)
         .run();
 }
@@ -0,0 +1,277 @@
+use std::ffi::{c_char, c_void, CString};
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::time::Instant;
+
+use bevy::prelude::*;
+use renderdoc::{Entry, RenderDoc, V110, V111, V112, V120, V130, V140, V141};
+
+use crate::load::LIBRARY_NAME;
+use crate::RenderDocVersion;
+
+/// RenderDoc's API struct from version 1.6.0, which extends the 1.4.1 struct
+/// renderdoc-rs knows with the functions added since.
+#[repr(C)]
+struct Entry160 {
+    entry: Entry,
+    /// Added in 1.5.0.
+    show_replay_ui: Option<unsafe extern "C" fn() -> u32>,
+    /// Added in 1.6.0.
+    set_capture_title: Option<unsafe extern "C" fn(title: *const c_char)>,
+}
+
+/// The functions RenderDoc added after API version 1.4.1.
+#[derive(Clone, Copy, Debug, Default)]
+struct NewerFunctions {
+    show_replay_ui: Option<unsafe extern "C" fn() -> u32>,
+    set_capture_title: Option<unsafe extern "C" fn(title: *const c_char)>,
+}
+
+impl NewerFunctions {
+    /// Requests API `version` from the loaded library, which renderdoc-rs can't
+    /// do for versions newer than 1.4.1.
+    fn load(version: u32) -> Option<Self> {
+        type GetApiFn = unsafe extern "C" fn(version: u32, out: *mut *mut c_void) -> i32;
+
+        // SAFETY: renderdoc-rs has loaded the library already, so this only
+        // takes another reference to it. RenderDoc hands out a pointer to its
+        // API struct for `version`, which stays valid while the library is loaded.
+        unsafe {
+            let library = libloading::Library::new(LIBRARY_NAME).ok()?;
+            let get_api = library.get::<GetApiFn>(b"RENDERDOC_GetAPI\0").ok()?;
+            let mut entry = ptr::null_mut();
+            if get_api(version, &mut entry) != 1 || entry.is_null() {
+                return None;
+            }
+
+            // Only read the functions the requested version's struct contains.
+            let entry = &*(entry as *const Entry160);
+            Some(Self {
+                show_replay_ui: entry.show_replay_ui,
+                set_capture_title: if version >= 10600 {
+                    entry.set_capture_title
+                } else {
+                    None
+                },
+            })
+        }
+    }
+}
+
+/// The negotiated API version, with the renderdoc-rs wrapper for it.
+#[derive(Debug)]
+enum Api {
+    V110(RenderDoc<V110>),
+    V111(RenderDoc<V111>),
+    V112(RenderDoc<V112>),
+    V120(RenderDoc<V120>),
+    V130(RenderDoc<V130>),
+    V140(RenderDoc<V140>),
+    V141(RenderDoc<V141>),
+    /// renderdoc-rs stops at 1.4.1, which 1.5.0 and 1.6.0 only extend.
+    V150(RenderDoc<V141>),
+    V160(RenderDoc<V141>),
+}
+
+/// The RenderDoc API, at the newest version supported by the loaded library.
+///
+/// Dereferences to the baseline [`RenderDoc<RenderDocVersion>`] API. Use the
+/// version accessors, such as [`v120`](Self::v120), for newer calls, and
+/// [`capabilities`](Self::capabilities) to see what the library supports.
+#[derive(Debug)]
+pub struct RenderDocApi {
+    api: Api,
+    newer: NewerFunctions,
+    /// When the [`CaptureScope`](crate::CaptureScope) in progress began, if any.
+    pub(crate) scope_started: Option<Instant>,
+}
+
+impl RenderDocApi {
+    /// Loads RenderDoc, requesting the newest API version first and falling
+    /// back to older ones down to [`RenderDocVersion`].
+    pub fn new() -> Result<Self, renderdoc::Error> {
+        let api = RenderDoc::new()
+            .map(Api::V141)
+            .or_else(|_| RenderDoc::new().map(Api::V140))
+            .or_else(|_| RenderDoc::new().map(Api::V130))
+            .or_else(|_| RenderDoc::new().map(Api::V120))
+            .or_else(|_| RenderDoc::new().map(Api::V112))
+            .or_else(|_| RenderDoc::new().map(Api::V111))
+            .or_else(|_| RenderDoc::<RenderDocVersion>::new().map(Api::V110))?;
+
+        let (api, newer) = match api {
+            Api::V141(rd) => {
+                if let Some(newer) = NewerFunctions::load(10600) {
+                    (Api::V160(rd), newer)
+                } else if let Some(newer) = NewerFunctions::load(10500) {
+                    (Api::V150(rd), newer)
+                } else {
+                    (Api::V141(rd), NewerFunctions::default())
+                }
+            }
+            api => (api, NewerFunctions::default()),
+        };
+        Ok(Self {
+            api,
+            newer,
+            scope_started: None,
+        })
+    }
+
+    /// Returns what the negotiated API version supports.
+    pub fn capabilities(&self) -> RenderDocCapabilities {
+        let requested = match self.api {
+            Api::V110(_) => (1, 1, 0),
+            Api::V111(_) => (1, 1, 1),
+            Api::V112(_) => (1, 1, 2),
+            Api::V120(_) => (1, 2, 0),
+            Api::V130(_) => (1, 3, 0),
+            Api::V140(_) => (1, 4, 0),
+            Api::V141(_) => (1, 4, 1),
+            Api::V150(_) => (1, 5, 0),
+            Api::V160(_) => (1, 6, 0),
+        };
+        RenderDocCapabilities::for_version(requested, self.get_api_version())
+    }
+
+    /// Returns the 1.1.1 API, if the loaded library supports it.
+    pub fn v111(&mut self) -> Option<&mut RenderDoc<V111>> {
+        match &mut self.api {
+            Api::V110(_) => None,
+            Api::V111(rd) => Some(rd),
+            Api::V112(rd) => Some(rd),
+            Api::V120(rd) => Some(rd),
+            Api::V130(rd) => Some(rd),
+            Api::V140(rd) => Some(rd),
+            Api::V141(rd) | Api::V150(rd) | Api::V160(rd) => Some(rd),
+        }
+    }
+
+    /// Returns the 1.1.2 API, if the loaded library supports it.
+    pub fn v112(&mut self) -> Option<&mut RenderDoc<V112>> {
+        match &mut self.api {
+            Api::V110(_) | Api::V111(_) => None,
+            Api::V112(rd) => Some(rd),
+            Api::V120(rd) => Some(rd),
+            Api::V130(rd) => Some(rd),
+            Api::V140(rd) => Some(rd),
+            Api::V141(rd) | Api::V150(rd) | Api::V160(rd) => Some(rd),
+        }
+    }
+
+    /// Returns the 1.2.0 API, if the loaded library supports it.
+    pub fn v120(&mut self) -> Option<&mut RenderDoc<V120>> {
+        match &mut self.api {
+            Api::V110(_) | Api::V111(_) | Api::V112(_) => None,
+            Api::V120(rd) => Some(rd),
+            Api::V130(rd) => Some(rd),
+            Api::V140(rd) => Some(rd),
+            Api::V141(rd) | Api::V150(rd) | Api::V160(rd) => Some(rd),
+        }
+    }
+
+    /// Returns the 1.4.0 API, if the loaded library supports it.
+    pub fn v140(&mut self) -> Option<&mut RenderDoc<V140>> {
+        match &mut self.api {
+            Api::V140(rd) => Some(rd),
+            Api::V141(rd) | Api::V150(rd) | Api::V160(rd) => Some(rd),
+            _ => None,
+        }
+    }
+
+    /// Brings a connected replay UI to the foreground.
+    ///
+    /// Returns `false` if no replay UI is connected, or the loaded library is
+    /// older than API 1.5.0.
+    pub fn show_replay_ui(&mut self) -> bool {
+        match self.newer.show_replay_ui {
+            // SAFETY: RenderDoc provides the function from API 1.5.0 on.
+            Some(show_replay_ui) => unsafe { show_replay_ui() == 1 },
+            None => false,
+        }
+    }
+
+    /// Sets the title of the capture in progress, or of the next capture if
+    /// none is in progress.
+    ///
+    /// Returns `false` if the loaded library is older than API 1.6.0.
+    pub fn set_capture_title(&mut self, title: &str) -> bool {
+        let set_capture_title = match self.newer.set_capture_title {
+            Some(set_capture_title) => set_capture_title,
+            None => return false,
+        };
+        let title = match CString::new(title) {
+            Ok(title) => title,
+            Err(_) => return false,
+        };
+        // SAFETY: RenderDoc provides the function from API 1.6.0 on, and copies the title.
+        unsafe { set_capture_title(title.as_ptr()) };
+        true
+    }
+}
+
+impl Deref for RenderDocApi {
+    type Target = RenderDoc<RenderDocVersion>;
+
+    fn deref(&self) -> &Self::Target {
+        match &self.api {
+            Api::V110(rd) => rd,
+            Api::V111(rd) => rd,
+            Api::V112(rd) => rd,
+            Api::V120(rd) => rd,
+            Api::V130(rd) => rd,
+            Api::V140(rd) => rd,
+            Api::V141(rd) | Api::V150(rd) | Api::V160(rd) => rd,
+        }
+    }
+}
+
+impl DerefMut for RenderDocApi {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match &mut self.api {
+            Api::V110(rd) => rd,
+            Api::V111(rd) => rd,
+            Api::V112(rd) => rd,
+            Api::V120(rd) => rd,
+            Api::V130(rd) => rd,
+            Api::V140(rd) => rd,
+            Api::V141(rd) | Api::V150(rd) | Api::V160(rd) => rd,
+        }
+    }
+}
+
+/// Which optional RenderDoc features the loaded library supports.
+///
+/// Inserted as a resource once RenderDoc is loaded.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderDocCapabilities {
+    /// The API version the plugin negotiated with the library.
+    pub requested_version: (u32, u32, u32),
+    /// The API version the library reports, which may be newer.
+    pub api_version: (u32, u32, u32),
+    /// Whether the connection state of the replay UI can be queried.
+    pub target_control: bool,
+    /// Whether comments can be stored in capture files.
+    pub capture_comments: bool,
+    /// Whether in-progress captures can be discarded.
+    pub discard_capture: bool,
+    /// Whether a connected replay UI can be brought to the foreground.
+    pub show_replay_ui: bool,
+    /// Whether captures can be given a title.
+    pub capture_title: bool,
+}
+
+impl RenderDocCapabilities {
+    /// Returns the capabilities of the negotiated `requested` version.
+    pub fn for_version(requested: (u32, u32, u32), api_version: (u32, u32, u32)) -> Self {
+        Self {
+            requested_version: requested,
+            api_version,
+            target_control: requested >= (1, 1, 1),
+            capture_comments: requested >= (1, 2, 0),
+            discard_capture: requested >= (1, 4, 0),
+            show_replay_ui: requested >= (1, 5, 0),
+            capture_title: requested >= (1, 6, 0),
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::time::{Duration, Instant, SystemTime};
+
+use renderdoc::{CaptureOption, DevicePointer, InputButton, OverlayBits, WindowHandle};
+
+use crate::{RenderDocApi, RenderDocCapabilities};
+
+/// The subset of the RenderDoc in-application API used by this crate.
+///
+/// [`RenderDocApi`] implements this trait directly. Other implementations, such
+/// as [`MockBackend`](crate::testing::MockBackend), allow the plugin logic to
+/// run without RenderDoc being installed.
+pub trait CaptureBackend: 'static {
+    /// Returns which optional features this backend supports.
+    fn capabilities(&self) -> RenderDocCapabilities;
+
+    /// Captures the next frame from the active window and device.
+    fn trigger_capture(&mut self);
+
+    /// Captures the next `frames` frames, writing a separate capture file for each.
+    fn trigger_multi_frame_capture(&mut self, frames: u32);
+
+    /// Begins capturing on the given device/window combination.
+    ///
+    /// Null pointers act as wildcards.
+    fn start_frame_capture(&mut self, device: DevicePointer, window: WindowHandle);
+
+    /// Ends a capture started with [`start_frame_capture`](Self::start_frame_capture)
+    /// and saves it to disk.
+    fn end_frame_capture(&mut self, device: DevicePointer, window: WindowHandle);
+
+    /// Ends a capture started with [`start_frame_capture`](Self::start_frame_capture)
+    /// without saving it.
+    ///
+    /// Returns `false` if no capture was in progress, or the loaded RenderDoc
+    /// API does not support discarding captures.
+    fn discard_frame_capture(&mut self, device: DevicePointer, window: WindowHandle) -> bool;
+
+    /// Makes the given device/window combination the one captured by
+    /// [`trigger_capture`](Self::trigger_capture) and RenderDoc's capture keys.
+    fn set_active_window(&mut self, device: DevicePointer, window: WindowHandle);
+
+    /// Returns whether a frame capture is currently in progress.
+    fn is_frame_capturing(&self) -> bool;
+
+    /// Returns when the [`CaptureScope`](crate::CaptureScope) in progress
+    /// began, if any.
+    fn scope_started(&self) -> Option<Instant>;
+
+    /// Records when a [`CaptureScope`](crate::CaptureScope) began, or that
+    /// none is in progress.
+    fn set_scope_started(&mut self, started: Option<Instant>);
+
+    /// Returns the number of captures taken so far.
+    fn get_num_captures(&self) -> u32;
+
+    /// Returns the path and timestamp of the capture at `index`.
+    fn get_capture(&self, index: u32) -> Option<(PathBuf, SystemTime)>;
+
+    /// Stores `comments` in the capture file at `path`, or in the most recent
+    /// capture if `path` is [`None`].
+    ///
+    /// Returns `false` if the loaded RenderDoc API does not support comments.
+    fn set_capture_file_comments(&mut self, path: Option<&Path>, comments: &str) -> bool;
+
+    /// Sets the title of the capture in progress.
+    ///
+    /// Returns `false` if the loaded RenderDoc API does not support capture titles.
+    fn set_capture_title(&mut self, title: &str) -> bool;
+
+    /// Launches the replay UI, returning its process id on success.
+    ///
+    /// `extra_opts` is passed to the replay UI's command line.
+    fn launch_replay_ui(&mut self, connect_immediately: bool, extra_opts: Option<&str>) -> Option<u32>;
+
+    /// Returns whether a replay UI is connected to this application.
+    ///
+    /// Always `false` if the loaded RenderDoc API does not support target control.
+    fn is_target_control_connected(&mut self) -> bool;
+
+    /// Brings a connected replay UI to the foreground.
+    ///
+    /// Returns `false` if no replay UI is connected, or the loaded RenderDoc
+    /// API does not support showing it.
+    fn show_replay_ui(&mut self) -> bool;
+
+    /// Sets a capture option, returning `false` if the loaded RenderDoc
+    /// library does not know it.
+    fn set_capture_option(&mut self, option: CaptureOption, value: u32) -> bool;
+
+    /// Returns the template new capture files are named after.
+    fn get_capture_file_path_template(&self) -> PathBuf;
+
+    /// Sets the template new capture files are named after.
+    fn set_capture_file_path_template(&mut self, template: &Path);
+
+    /// Returns the currently visible overlay bits.
+    fn get_overlay_bits(&self) -> OverlayBits;
+
+    /// Masks the overlay bits with `and`, then enables the bits in `or`.
+    fn mask_overlay_bits(&mut self, and: OverlayBits, or: OverlayBits);
+
+    /// Sets the keys RenderDoc listens to for captures.
+    fn set_capture_keys(&mut self, keys: &[InputButton]);
+
+    /// Sets the keys RenderDoc listens to for cycling the active window.
+    fn set_focus_toggle_keys(&mut self, keys: &[InputButton]);
+}
+
+impl CaptureBackend for RenderDocApi {
+    fn capabilities(&self) -> RenderDocCapabilities {
+        RenderDocApi::capabilities(self)
+    }
+
+    fn trigger_capture(&mut self) {
+        (**self).trigger_capture();
+    }
+
+    fn trigger_multi_frame_capture(&mut self, frames: u32) {
+        (**self).trigger_multi_frame_capture(frames);
+    }
+
+    fn start_frame_capture(&mut self, device: DevicePointer, window: WindowHandle) {
+        (**self).start_frame_capture(device, window);
+    }
+
+    fn end_frame_capture(&mut self, device: DevicePointer, window: WindowHandle) {
+        (**self).end_frame_capture(device, window);
+    }
+
+    fn discard_frame_capture(&mut self, device: DevicePointer, window: WindowHandle) -> bool {
+        self.v140()
+            .is_some_and(|rd| rd.discard_frame_capture(device, window))
+    }
+
+    fn set_active_window(&mut self, device: DevicePointer, window: WindowHandle) {
+        (**self).set_active_window(device, window);
+    }
+
+    fn is_frame_capturing(&self) -> bool {
+        (**self).is_frame_capturing()
+    }
+
+    fn scope_started(&self) -> Option<Instant> {
+        self.scope_started
+    }
+
+    fn set_scope_started(&mut self, started: Option<Instant>) {
+        self.scope_started = started;
+    }
+
+    fn get_num_captures(&self) -> u32 {
+        (**self).get_num_captures()
+    }
+
+    fn get_capture(&self, index: u32) -> Option<(PathBuf, SystemTime)> {
+        // `RenderDoc::get_capture` hands a buffer it still owns to `CString::from_raw`,
+        // so query the path length first and fill our own buffer instead.
+        unsafe {
+            let get_capture = (*self.raw_api()).GetCapture?;
+
+            let mut len = 0;
+            if get_capture(index, ptr::null_mut(), &mut len, ptr::null_mut()) != 1 {
+                return None;
+            }
+
+            let mut path = vec![0u8; len as usize + 1];
+            let mut time = 0;
+            if get_capture(index, path.as_mut_ptr().cast(), &mut len, &mut time) != 1 {
+                return None;
+            }
+
+            let path = CStr::from_bytes_until_nul(&path).ok()?.to_str().ok()?;
+            Some((path.into(), SystemTime::UNIX_EPOCH + Duration::from_secs(time)))
+        }
+    }
+
+    fn set_capture_file_comments(&mut self, path: Option<&Path>, comments: &str) -> bool {
+        match self.v120() {
+            Some(rd) => {
+                rd.set_capture_file_comments(path.and_then(Path::to_str), comments);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn set_capture_title(&mut self, title: &str) -> bool {
+        RenderDocApi::set_capture_title(self, title)
+    }
+
+    fn launch_replay_ui(&mut self, connect_immediately: bool, extra_opts: Option<&str>) -> Option<u32> {
+        (**self).launch_replay_ui(connect_immediately, extra_opts).ok()
+    }
+
+    fn is_target_control_connected(&mut self) -> bool {
+        self.v111().is_some_and(|rd| rd.is_target_control_connected())
+    }
+
+    fn show_replay_ui(&mut self) -> bool {
+        RenderDocApi::show_replay_ui(self)
+    }
+
+    fn set_capture_option(&mut self, option: CaptureOption, value: u32) -> bool {
+        // `RenderDoc::set_capture_option_u32` panics on options the library rejects.
+        unsafe {
+            match (*self.raw_api()).SetCaptureOptionU32 {
+                Some(set_capture_option) => set_capture_option(option as u32, value) == 1,
+                None => false,
+            }
+        }
+    }
+
+    fn get_capture_file_path_template(&self) -> PathBuf {
+        (**self).get_log_file_path_template().to_owned()
+    }
+
+    fn set_capture_file_path_template(&mut self, template: &Path) {
+        (**self).set_log_file_path_template(template);
+    }
+
+    fn get_overlay_bits(&self) -> OverlayBits {
+        (**self).get_overlay_bits()
+    }
+
+    fn mask_overlay_bits(&mut self, and: OverlayBits, or: OverlayBits) {
+        (**self).mask_overlay_bits(and, or);
+    }
+
+    fn set_capture_keys(&mut self, keys: &[InputButton]) {
+        (**self).set_capture_keys(keys);
+    }
+
+    fn set_focus_toggle_keys(&mut self, keys: &[InputButton]) {
+        (**self).set_focus_toggle_keys(keys);
+    }
+}
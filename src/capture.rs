@@ -0,0 +1,536 @@
+#[cfg(feature = "capture")]
+use std::collections::VecDeque;
+#[cfg(feature = "capture")]
+use std::ffi::c_void;
+#[cfg(feature = "capture")]
+use std::path::Path;
+use std::path::PathBuf;
+#[cfg(feature = "capture")]
+use std::ptr;
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+#[cfg(feature = "capture")]
+use bevy::render::renderer::RenderDevice;
+use bevy::window::WindowId;
+
+#[cfg(feature = "capture")]
+use crate::replay::ReplayUi;
+#[cfg(feature = "capture")]
+use crate::scope::ArmedScope;
+#[cfg(feature = "capture")]
+use crate::{window, CaptureBackend, RenderDocSettings};
+
+/// How a [`CaptureRequest`] spanning several frames is captured.
+///
+/// # Examples
+#[cfg_attr(feature = "capture", doc = "```rust")]
+#[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let mut app = App::new();
+/// app.add_plugins(MinimalPlugins)
+///     .add_plugin(MockRenderDocPlugin::default());
+///
+/// app.world.send_event(CaptureRequest::span(3));
+/// for _ in 0..4 {
+///     app.update();
+/// }
+///
+/// let mock = app.world.non_send_resource::<MockBackend>();
+/// assert_eq!(mock.frames_of(&BackendCall::StartFrameCapture), vec![0]);
+/// assert_eq!(mock.frames_of(&BackendCall::EndFrameCapture), vec![3]);
+/// assert_eq!(mock.get_num_captures(), 1);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CaptureMode {
+    /// Every frame is written to its own capture file.
+    #[default]
+    Separate,
+    /// All frames are written to a single capture file, bracketed by
+    /// starting and ending a frame capture manually.
+    Span,
+    /// Every frame is written to its own capture file, holding only the work
+    /// between the [`RenderDocCaptureScope`](crate::RenderDocCaptureScope)
+    /// nodes in the render graph.
+    Scope,
+}
+
+/// Event that asks the plugin to capture upcoming frames.
+///
+/// Any system can send this event; the request is picked up in
+/// [`RenderDocSystem::Requests`](crate::RenderDocSystem::Requests). Each captured
+/// frame is reported through a [`CaptureCompleted`] event once RenderDoc has
+/// written it.
+///
+/// # Examples
+/// ```rust, no_run
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// fn capture_on_space(keys: Res<Input<KeyCode>>, mut requests: EventWriter<CaptureRequest>) {
+///     if keys.just_pressed(KeyCode::Space) {
+///         requests.send(CaptureRequest {
+///             comment: Some("Jump animation".into()),
+///             ..default()
+///         });
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CaptureRequest {
+    /// The number of consecutive frames to capture.
+    ///
+    /// Defaults to a single frame.
+    pub frames: Option<u32>,
+    /// Whether the frames are captured to separate files or a single one.
+    pub mode: CaptureMode,
+    /// The title of the capture, shown by the replay UI.
+    ///
+    /// Libraries older than RenderDoc API 1.6.0 can't store titles, so the
+    /// title is shown first in the capture's comments instead.
+    ///
+    /// # Examples
+    #[cfg_attr(feature = "capture", doc = "```rust")]
+    #[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+    /// # use bevy::prelude::*;
+    /// # use bevy_renderdoc::testing::*;
+    /// # use bevy_renderdoc::*;
+    /// #
+    /// fn comments(api_version: (u32, u32, u32)) -> (Vec<u64>, String) {
+    ///     let mut backend = MockBackend::default();
+    ///     backend.api_version = api_version;
+    ///
+    ///     let mut app = App::new();
+    ///     app.insert_resource(RenderDocSettings {
+    ///         write_sidecars: false,
+    ///         ..default()
+    ///     })
+    ///     .add_plugins(MinimalPlugins)
+    ///     .add_plugin(MockRenderDocPlugin {
+    ///         backend,
+    ///         ..default()
+    ///     });
+    ///
+    ///     app.world.send_event(CaptureRequest {
+    ///         title: Some("Explosion".into()),
+    ///         comment: Some("Particles flicker".into()),
+    ///         ..CaptureRequest::span(2)
+    ///     });
+    ///     for _ in 0..4 {
+    ///         app.update();
+    ///     }
+    ///
+    ///     let mock = app.world.non_send_resource::<MockBackend>();
+    ///     let comments = mock.calls.iter().find_map(|(_, call)| match call {
+    ///         BackendCall::SetCaptureFileComments { comments, .. } => Some(comments.clone()),
+    ///         _ => None,
+    ///     });
+    ///     (mock.frames_of(&BackendCall::SetCaptureTitle("Explosion".into())), comments.unwrap())
+    /// }
+    ///
+    /// // Set while the capture is in progress.
+    /// assert_eq!(comments((1, 6, 0)), (vec![0, 1], "Particles flicker".into()));
+    /// assert_eq!(comments((1, 4, 1)), (vec![0, 1], "Explosion\n\nParticles flicker".into()));
+    /// ```
+    pub title: Option<String>,
+    /// Comments stored in the capture file.
+    pub comment: Option<String>,
+    /// The window to capture. Defaults to the window RenderDoc considers active.
+    ///
+    /// Ignored by [`CaptureMode::Scope`], which captures everything between
+    /// the scope nodes.
+    pub window: Option<WindowId>,
+    /// Whether to open the capture in the replay UI once it has been written,
+    /// following [`RenderDocSettings::replay_ui`].
+    ///
+    /// Only the first capture of a multi-frame request is opened.
+    pub open_ui: bool,
+}
+
+impl CaptureRequest {
+    /// Requests `frames` consecutive frames, each in its own capture file.
+    pub fn multi_frame(frames: u32) -> Self {
+        Self {
+            frames: Some(frames),
+            ..default()
+        }
+    }
+
+    /// Requests a single capture file spanning `frames` consecutive frames.
+    pub fn span(frames: u32) -> Self {
+        Self {
+            frames: Some(frames),
+            mode: CaptureMode::Span,
+            ..default()
+        }
+    }
+
+    /// Requests the work between the [`RenderDocCaptureScope`](crate::RenderDocCaptureScope)
+    /// nodes in the next frame.
+    pub fn scope() -> Self {
+        Self {
+            mode: CaptureMode::Scope,
+            ..default()
+        }
+    }
+}
+
+/// Event sent once RenderDoc has written a new capture to disk.
+///
+/// # Examples
+/// Requests for the same frame are merged, since RenderDoc writes a single
+/// capture for them.
+#[cfg_attr(feature = "capture", doc = "```rust")]
+#[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let mut app = App::new();
+/// app.add_plugins(MinimalPlugins)
+///     .add_plugin(MockRenderDocPlugin::default());
+///
+/// app.world.send_event(CaptureRequest {
+///     title: Some("Explosion".into()),
+///     ..default()
+/// });
+/// app.world.send_event(CaptureRequest {
+///     comment: Some("Particles".into()),
+///     ..default()
+/// });
+/// app.update();
+/// app.world.send_event(CaptureRequest {
+///     title: Some("Aftermath".into()),
+///     ..default()
+/// });
+/// app.update();
+///
+/// let completed = app.world.resource::<Events<CaptureCompleted>>();
+/// let mut reader = completed.get_reader();
+/// let captures: Vec<_> = reader
+///     .iter(completed)
+///     .map(|c| (c.frame, c.title.as_deref(), c.comment.as_deref()))
+///     .collect();
+/// assert_eq!(
+///     captures,
+///     vec![(0, Some("Explosion"), Some("Particles")), (1, Some("Aftermath"), None)]
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct CaptureCompleted {
+    /// Absolute path to the `.rdc` file.
+    pub path: PathBuf,
+    /// The time RenderDoc took the capture.
+    pub timestamp: SystemTime,
+    /// The bevy frame the capture came from, counted from `0` at the first update.
+    pub frame: u64,
+    /// The title of the [`CaptureRequest`] that produced this capture, if any.
+    pub title: Option<String>,
+    /// The comment of the [`CaptureRequest`] that produced this capture, if any.
+    pub comment: Option<String>,
+}
+
+/// A requested capture RenderDoc has not reported yet.
+#[cfg(feature = "capture")]
+struct PendingCapture {
+    frame: u64,
+    /// The frame RenderDoc writes the capture on, later than `frame` for span captures.
+    done: u64,
+    mode: CaptureMode,
+    title: Option<String>,
+    comment: Option<String>,
+    open_ui: bool,
+}
+
+#[cfg(feature = "capture")]
+impl PendingCapture {
+    /// Folds a request for the same capture into this one.
+    fn merge(&mut self, other: PendingCapture) {
+        if self.title.is_none() {
+            self.title = other.title;
+        }
+        self.comment = match (self.comment.take(), other.comment) {
+            (Some(comment), Some(other)) => Some(format!("{}\n\n{}", comment, other)),
+            (comment, other) => comment.or(other),
+        };
+        self.open_ui |= other.open_ui;
+    }
+}
+
+/// How many frames after it was due a requested capture is still waited for.
+///
+/// Some requests never turn into a capture, such as scope requests without
+/// scope nodes in the render graph, or triggers RenderDoc drops.
+#[cfg(feature = "capture")]
+const PENDING_FRAMES: u64 = 5;
+
+/// Matches captures reported by RenderDoc to the requests that caused them.
+#[cfg(feature = "capture")]
+#[derive(Resource)]
+pub(crate) struct CaptureTracker {
+    frame: u64,
+    known_captures: u32,
+    pending: VecDeque<PendingCapture>,
+    /// Frames left in the span capture in progress, if any.
+    span_frames: Option<u32>,
+    /// The last frame slowed down by capturing, if any.
+    busy_until: Option<u64>,
+}
+
+#[cfg(feature = "capture")]
+impl CaptureTracker {
+    pub(crate) fn new(known_captures: u32) -> Self {
+        Self {
+            frame: 0,
+            known_captures,
+            pending: VecDeque::new(),
+            span_frames: None,
+            busy_until: None,
+        }
+    }
+
+    /// Returns the current frame, counted from `0` at the first update.
+    pub(crate) fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns whether `frame` was slowed down by capturing, or by writing a capture.
+    pub(crate) fn was_capturing(&self, frame: u64) -> bool {
+        self.busy_until.is_some_and(|busy_until| frame <= busy_until)
+    }
+
+    /// Marks the frames up to `frame` as slowed down by capturing.
+    fn busy_until(&mut self, frame: u64) {
+        self.busy_until = Some(self.busy_until.map_or(frame, |busy_until| busy_until.max(frame)));
+    }
+
+    /// Expects a capture for `capture`'s request, merging it with a request
+    /// for the same capture, since RenderDoc writes only one.
+    fn expect(&mut self, capture: PendingCapture) {
+        // RenderDoc may write the capture while presenting the next frame.
+        self.busy_until(capture.done + 1);
+        let same = self
+            .pending
+            .iter_mut()
+            .find(|pending| pending.done == capture.done && pending.mode == capture.mode);
+        match same {
+            Some(pending) => pending.merge(capture),
+            None => {
+                let index = self.pending.partition_point(|pending| pending.done <= capture.done);
+                self.pending.insert(index, capture);
+            }
+        }
+    }
+
+    /// Expects the capture RenderDoc takes on its own capture keys this frame.
+    pub(crate) fn expect_native_capture(&mut self) {
+        self.expect(PendingCapture {
+            frame: self.frame,
+            done: self.frame,
+            mode: CaptureMode::Separate,
+            title: None,
+            comment: None,
+            open_ui: true,
+        });
+    }
+
+    /// Forgets requests that should have turned into a capture by now.
+    fn expire(&mut self) {
+        let frame = self.frame;
+        self.pending.retain(|pending| pending.done + PENDING_FRAMES >= frame);
+    }
+}
+
+/// Requests the frames listed in [`RenderDocSettings::capture_at_frames`].
+#[cfg(feature = "capture")]
+pub(crate) fn capture_scheduled_frames(
+    settings: Res<RenderDocSettings>,
+    tracker: Res<CaptureTracker>,
+    mut requests: EventWriter<CaptureRequest>,
+) {
+    if settings.capture_at_frames.contains(&tracker.frame) {
+        requests.send(CaptureRequest {
+            title: Some(format!("Scheduled capture of frame {}", tracker.frame)),
+            ..default()
+        });
+    }
+}
+
+#[cfg(feature = "capture")]
+pub(crate) fn request_captures<B: CaptureBackend>(
+    mut requests: EventReader<CaptureRequest>,
+    mut rd: NonSendMut<B>,
+    mut tracker: ResMut<CaptureTracker>,
+    mut armed_scope: Option<ResMut<ArmedScope>>,
+    windows: Option<Res<Windows>>,
+    device: Option<Res<RenderDevice>>,
+) {
+    for request in requests.iter() {
+        let frames = request.frames.unwrap_or(1).max(1);
+        let target = request.window.and_then(|id| {
+            let target = window::capture_target(windows.as_deref(), device.as_deref(), id);
+            if target.is_none() {
+                warn!("Cannot capture window {:?}, capturing the active window instead", id);
+            }
+            target
+        });
+
+        let captures = match request.mode {
+            CaptureMode::Separate => {
+                if let Some((device, window)) = target {
+                    rd.set_active_window(device, window);
+                }
+                if frames == 1 {
+                    rd.trigger_capture();
+                } else {
+                    rd.trigger_multi_frame_capture(frames);
+                }
+                frames
+            }
+            CaptureMode::Span => {
+                if tracker.span_frames.is_some() || rd.is_frame_capturing() {
+                    warn!("Ignoring RenderDoc span capture request, a frame capture is already in progress");
+                    continue;
+                }
+                let (device, window) = target.unwrap_or_else(|| (wildcard_device(), ptr::null()));
+                rd.start_frame_capture(device, window);
+                tracker.span_frames = Some(frames);
+                1
+            }
+            CaptureMode::Scope => match armed_scope.as_mut() {
+                Some(armed_scope) => {
+                    // Overlapping requests capture the same frames.
+                    armed_scope.frames = armed_scope.frames.max(frames);
+                    if request.title.is_some() {
+                        armed_scope.title = request.title.clone();
+                    }
+                    frames
+                }
+                None => {
+                    warn!("Ignoring RenderDoc scope capture request, RenderDocRenderPlugin is not active");
+                    continue;
+                }
+            },
+        };
+
+        let frame = tracker.frame;
+        for i in 0..captures as u64 {
+            let done = match request.mode {
+                CaptureMode::Span => frame + frames as u64,
+                _ => frame + i,
+            };
+            tracker.expect(PendingCapture {
+                frame: frame + i,
+                done,
+                mode: request.mode,
+                title: request.title.clone(),
+                comment: request.comment.clone(),
+                open_ui: request.open_ui && i == 0,
+            });
+        }
+    }
+}
+
+/// Sets the title of the capture in progress, if its request has one.
+///
+/// RenderDoc only stores titles set while the capture is in progress, which
+/// for triggered captures is the frame after the request. Scope captures are
+/// titled by their render graph nodes instead.
+#[cfg(feature = "capture")]
+pub(crate) fn title_captures<B: CaptureBackend>(mut rd: NonSendMut<B>, tracker: Res<CaptureTracker>) {
+    if !rd.is_frame_capturing() {
+        return;
+    }
+
+    // RenderDoc applies the title to the first capture to end.
+    let title = tracker
+        .pending
+        .iter()
+        .find(|pending| pending.mode != CaptureMode::Scope)
+        .and_then(|pending| pending.title.as_deref());
+    if let Some(title) = title {
+        rd.set_capture_title(title);
+    }
+}
+
+/// Ends span captures once all of their frames have been rendered.
+///
+/// Runs at the start of a frame, since the previous frame is rendered after
+/// the main world's update finishes.
+#[cfg(feature = "capture")]
+pub(crate) fn count_span_frames<B: CaptureBackend>(
+    mut rd: NonSendMut<B>,
+    mut tracker: ResMut<CaptureTracker>,
+) {
+    if let Some(frames) = tracker.span_frames.as_mut() {
+        *frames -= 1;
+        if *frames == 0 {
+            tracker.span_frames = None;
+            rd.end_frame_capture(wildcard_device(), ptr::null());
+        }
+    }
+}
+
+#[cfg(feature = "capture")]
+pub(crate) fn complete_captures<B: CaptureBackend>(
+    mut rd: NonSendMut<B>,
+    settings: Res<RenderDocSettings>,
+    mut tracker: ResMut<CaptureTracker>,
+    mut replay_ui: ResMut<ReplayUi>,
+    mut completed: EventWriter<CaptureCompleted>,
+) {
+    tracker.expire();
+
+    let num_captures = rd.get_num_captures();
+    while tracker.known_captures < num_captures {
+        let index = tracker.known_captures;
+        tracker.known_captures += 1;
+        let frame = tracker.frame;
+        tracker.busy_until(frame + 1);
+
+        let (path, timestamp) = match rd.get_capture(index) {
+            Some(capture) => capture,
+            None => continue,
+        };
+        let path = absolute(&path);
+
+        // Captures RenderDoc took on its own, such as through the replay UI, have no matching request.
+        let (frame, title, comment, open_ui) = match tracker.pending.pop_front() {
+            Some(pending) => (pending.frame, pending.title, pending.comment, pending.open_ui),
+            None => (tracker.frame, None, None, false),
+        };
+
+        info!("RenderDoc capture saved to {}", path.display());
+        if open_ui {
+            replay_ui.open(&mut *rd, &path, settings.replay_ui);
+        }
+        completed.send(CaptureCompleted {
+            path,
+            timestamp,
+            frame,
+            title,
+            comment,
+        });
+    }
+
+    tracker.frame += 1;
+}
+
+#[cfg(feature = "capture")]
+pub(crate) fn wildcard_device() -> renderdoc::DevicePointer {
+    ptr::null::<c_void>().into()
+}
+
+#[cfg(feature = "capture")]
+pub(crate) fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_owned();
+    }
+
+    std::env::current_dir()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|_| path.to_owned())
+}
@@ -0,0 +1,104 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+
+#[cfg(feature = "capture")]
+use crate::replay::ReplayUi;
+#[cfg(feature = "capture")]
+use crate::CaptureBackend;
+use crate::CaptureRequest;
+
+/// A thread-safe handle for controlling RenderDoc.
+///
+/// Unlike [`RenderDocResource`](crate::RenderDocResource), this is a regular
+/// resource, so systems using it can run in parallel. It is also inserted into
+/// the render world by [`RenderDocRenderPlugin`](crate::RenderDocRenderPlugin),
+/// even if RenderDoc isn't loaded.
+/// Commands are queued and executed on the main thread in
+/// [`RenderDocSystem::Controller`](crate::RenderDocSystem::Controller), so
+/// commands sent from the render world take effect in the next frame.
+///
+/// # Examples
+#[cfg_attr(feature = "capture", doc = "```rust")]
+#[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// fn capture_first_frame(controller: Res<RenderDocController>, mut done: Local<bool>) {
+///     if !*done {
+///         controller.capture(CaptureRequest::default());
+///         *done = true;
+///     }
+/// }
+///
+/// let mut app = App::new();
+/// app.add_plugins(MinimalPlugins)
+///     .add_plugin(MockRenderDocPlugin::default())
+///     .add_system(capture_first_frame.before(RenderDocSystem::Controller));
+/// app.update();
+///
+/// let mock = app.world.non_send_resource::<MockBackend>();
+/// assert_eq!(mock.triggered_frames(), vec![0]);
+/// ```
+#[derive(Resource, Clone, Debug)]
+pub struct RenderDocController {
+    sender: Sender<Command>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(not(feature = "capture"), allow(dead_code))]
+enum Command {
+    Capture(CaptureRequest),
+    LaunchReplayUi,
+}
+
+impl RenderDocController {
+    /// Returns a controller and the receiving end its commands are executed from.
+    pub(crate) fn new() -> (Self, ControllerCommands) {
+        let (sender, receiver) = channel();
+        (Self { sender }, ControllerCommands(Mutex::new(receiver)))
+    }
+
+    /// Requests a capture, like sending a [`CaptureRequest`] event.
+    pub fn capture(&self, request: CaptureRequest) {
+        self.send(Command::Capture(request));
+    }
+
+    /// Launches the replay UI without opening a capture, unless one is
+    /// already running or connected.
+    pub fn launch_replay_ui(&self) {
+        self.send(Command::LaunchReplayUi);
+    }
+
+    fn send(&self, command: Command) {
+        // Only fails once the app, and with it the executing system, is gone.
+        let _ = self.sender.send(command);
+    }
+}
+
+/// The commands sent through every [`RenderDocController`].
+#[derive(Resource)]
+#[cfg_attr(not(feature = "capture"), allow(dead_code))]
+pub(crate) struct ControllerCommands(Mutex<Receiver<Command>>);
+
+#[cfg(feature = "capture")]
+pub(crate) fn execute_controller_commands<B: CaptureBackend>(
+    commands: Res<ControllerCommands>,
+    mut rd: NonSendMut<B>,
+    mut replay_ui: ResMut<ReplayUi>,
+    mut requests: EventWriter<CaptureRequest>,
+) {
+    let receiver = match commands.0.lock() {
+        Ok(receiver) => receiver,
+        Err(_) => return,
+    };
+
+    for command in receiver.try_iter() {
+        match command {
+            Command::Capture(request) => requests.send(request),
+            Command::LaunchReplayUi => replay_ui.launch(&mut *rd),
+        }
+    }
+}
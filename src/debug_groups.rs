@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use bevy::render::render_graph::{
+    EmptyNode, Node, NodeId, NodeRunError, RenderGraph, RenderGraphContext, SlotInfo, SlotValue,
+};
+use bevy::render::renderer::RenderContext;
+
+use crate::RenderDocCaptureScope;
+
+/// The names of the nodes that open and close a sub-graph's debug group.
+const SUB_GRAPH_BEGIN: &str = "renderdoc_debug_group_begin";
+const SUB_GRAPH_END: &str = "renderdoc_debug_group_end";
+
+/// Runs a render graph node inside a debug group named after it.
+///
+/// Bevy runs each render phase in its own labelled render pass, which wgpu
+/// turns into a nested marker region, so phases show up inside their node's group.
+/// Sub-graphs a node runs, such as `core_3d` run by the camera driver, only
+/// start once the node returns, so they get their own [`SubGraphGroupNode`]s.
+struct DebugGroupNode {
+    label: String,
+    node: Box<dyn Node>,
+}
+
+impl Node for DebugGroupNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        self.node.input()
+    }
+
+    fn output(&self) -> Vec<SlotInfo> {
+        self.node.output()
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.node.update(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view = graph.inputs().iter().find_map(|input| match input {
+            SlotValue::Entity(entity) => Some(*entity),
+            _ => None,
+        });
+        let label = match view {
+            Some(view) => format!("{} (view {:?})", self.label, view),
+            None => self.label.clone(),
+        };
+
+        render_context.command_encoder.push_debug_group(&label);
+        let result = self.node.run(graph, render_context, world);
+        render_context.command_encoder.pop_debug_group();
+        result
+    }
+}
+
+/// Opens or closes the debug group around a whole sub-graph.
+///
+/// The `Begin` node runs before every other node of the sub-graph, taking the
+/// sub-graph's inputs to name the group after its view, and the `End` node
+/// runs after all of them.
+enum SubGraphGroupNode {
+    Begin {
+        label: String,
+        inputs: Vec<SlotInfo>,
+    },
+    End,
+}
+
+impl Node for SubGraphGroupNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        match self {
+            Self::Begin { inputs, .. } => inputs.clone(),
+            Self::End => Vec::new(),
+        }
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        _world: &World,
+    ) -> Result<(), NodeRunError> {
+        match self {
+            Self::Begin { label, .. } => {
+                let view = graph.inputs().iter().find_map(|input| match input {
+                    SlotValue::Entity(entity) => Some(*entity),
+                    _ => None,
+                });
+                let label = match view {
+                    Some(view) => format!("{} (view {:?})", label, view),
+                    None => label.clone(),
+                };
+                render_context.command_encoder.push_debug_group(&label);
+            }
+            Self::End => render_context.command_encoder.pop_debug_group(),
+        }
+        Ok(())
+    }
+}
+
+/// Wraps every node of the render graph and its sub-graphs in a [`DebugGroupNode`],
+/// and every sub-graph in a group of its own.
+///
+/// Runs every frame so nodes added after the plugin was built are wrapped as well.
+pub(crate) fn wrap_render_graph_nodes(mut graph: ResMut<RenderGraph>) {
+    wrap_nodes(graph.bypass_change_detection(), "main");
+}
+
+fn wrap_nodes(graph: &mut RenderGraph, graph_name: &str) {
+    for state in graph.iter_nodes_mut() {
+        // Scope nodes submit the encoder, which would split their group.
+        if state.node.is::<DebugGroupNode>()
+            || state.node.is::<SubGraphGroupNode>()
+            || state.node.is::<RenderDocCaptureScope>()
+        {
+            continue;
+        }
+
+        let node_name = state.name.as_deref().unwrap_or(state.type_name);
+        let label = format!("{}/{}", graph_name, node_name);
+        let node = std::mem::replace(&mut state.node, Box::new(EmptyNode));
+        state.node = Box::new(DebugGroupNode { label, node });
+    }
+
+    for (name, sub_graph) in graph.iter_sub_graphs_mut() {
+        wrap_nodes(sub_graph, name);
+        group_sub_graph(sub_graph, name);
+    }
+}
+
+/// Adds the [`SubGraphGroupNode`]s to `graph`, ordered around its other nodes.
+fn group_sub_graph(graph: &mut RenderGraph, graph_name: &str) {
+    // Scope nodes submit the encoder, which would split the group.
+    if graph
+        .iter_nodes()
+        .any(|state| state.node.is::<RenderDocCaptureScope>())
+    {
+        return;
+    }
+
+    let input_node = graph.input_node().map(|state| state.id);
+    let (begin, end) = match (
+        graph.get_node_id(SUB_GRAPH_BEGIN),
+        graph.get_node_id(SUB_GRAPH_END),
+    ) {
+        (Ok(begin), Ok(end)) => (begin, end),
+        _ => {
+            let inputs: Vec<SlotInfo> = graph
+                .input_node()
+                .map(|state| state.output_slots.iter().cloned().collect())
+                .unwrap_or_default();
+            let slots: Vec<_> = inputs.iter().map(|slot| slot.name.clone()).collect();
+            let label = graph_name.to_owned();
+            let begin = graph.add_node(SUB_GRAPH_BEGIN, SubGraphGroupNode::Begin { label, inputs });
+            let end = graph.add_node(SUB_GRAPH_END, SubGraphGroupNode::End);
+            for slot in slots {
+                // Can't fail, the slots mirror the input node's.
+                let _ =
+                    graph.add_slot_edge(RenderGraph::INPUT_NODE_NAME, slot.clone(), begin, slot);
+            }
+            (begin, end)
+        }
+    };
+
+    let nodes: Vec<NodeId> = graph
+        .iter_nodes()
+        .map(|state| state.id)
+        .filter(|&id| id != begin && id != end && Some(id) != input_node)
+        .collect();
+    for node in nodes {
+        // Fails for edges added in earlier frames.
+        let _ = graph.add_node_edge(begin, node);
+        let _ = graph.add_node_edge(node, end);
+    }
+}
@@ -0,0 +1,200 @@
+#[cfg(feature = "capture")]
+use std::collections::HashSet;
+#[cfg(feature = "capture")]
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "capture")]
+use bevy::{prelude::*, render::renderer::RenderDevice};
+
+#[cfg(feature = "capture")]
+use crate::spike::CaptureBudget;
+#[cfg(feature = "capture")]
+use crate::{CaptureRequest, RenderDocSettings};
+
+/// Configuration for capturing the frame after wgpu reports an uncaptured
+/// device error, such as a validation error.
+///
+/// Set through [`RenderDocSettings::capture_on_device_error`](crate::RenderDocSettings::capture_on_device_error).
+/// Each error is stored in the capture's comments. Errors already stored in a
+/// capture don't trigger another one, and errors reported during the cooldown
+/// are only logged.
+///
+/// # Examples
+/// ```rust, no_run
+/// # use std::time::Duration;
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// App::new()
+///     .insert_resource(RenderDocSettings {
+///         capture_on_device_error: Some(DeviceErrorCapture {
+///             cooldown: Duration::from_secs(60),
+///             max_captures: 1,
+///         }),
+///         ..default()
+///     })
+///     .add_plugins(DefaultPlugins.build().with_renderdoc())
+///     .run();
+/// ```
+///
+/// Reporting errors by hand:
+#[cfg_attr(feature = "capture", doc = "```rust")]
+#[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+/// # use std::time::Duration;
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let mut app = App::new();
+/// app.insert_resource(RenderDocSettings {
+///     capture_on_device_error: Some(DeviceErrorCapture {
+///         cooldown: Duration::from_secs(1),
+///         max_captures: 2,
+///     }),
+///     write_sidecars: false,
+///     ..default()
+/// })
+/// .init_resource::<Time>()
+/// .add_plugin(MockRenderDocPlugin::default());
+///
+/// let mut run_frame = |app: &mut App, millis: u64, errors: &[&str]| {
+///     for error in errors {
+///         report_device_error(app, *error);
+///     }
+///     let mut time = app.world.resource_mut::<Time>();
+///     let last = time.last_update().unwrap_or_else(|| time.startup());
+///     time.update_with_instant(last + Duration::from_millis(millis));
+///     app.update();
+/// };
+///
+/// run_frame(&mut app, 0, &["A"]);
+/// // Within the cooldown.
+/// run_frame(&mut app, 100, &["B"]);
+/// // Captured before.
+/// run_frame(&mut app, 1000, &["A"]);
+/// // Repeated errors are stored once.
+/// run_frame(&mut app, 100, &["B", "B", "C", "A"]);
+/// // Over the cap.
+/// run_frame(&mut app, 2000, &["D"]);
+///
+/// let mock = app.world.non_send_resource::<MockBackend>();
+/// assert_eq!(mock.triggered_frames(), vec![0, 3]);
+/// let comments: Vec<_> = mock
+///     .calls
+///     .iter()
+///     .filter_map(|(_, call)| match call {
+///         BackendCall::SetCaptureFileComments { comments, .. } => Some(comments.as_str()),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(comments, vec!["A", "B\n\nC"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DeviceErrorCapture {
+    /// The minimum time between two device error captures. Defaults to 10 seconds.
+    pub cooldown: Duration,
+    /// The maximum number of device error captures per session. Defaults to `5`.
+    pub max_captures: u32,
+}
+
+impl Default for DeviceErrorCapture {
+    fn default() -> Self {
+        Self {
+            cooldown: Duration::from_secs(10),
+            max_captures: 5,
+        }
+    }
+}
+
+/// Errors reported by wgpu's uncaptured error handler since the last frame.
+#[cfg(feature = "capture")]
+#[derive(Resource, Default)]
+pub(crate) struct DeviceErrors {
+    errors: Arc<Mutex<Vec<String>>>,
+    hooked: bool,
+    /// Errors stored in a capture so far, bounded by the per-session cap.
+    captured: HashSet<String>,
+    budget: CaptureBudget,
+}
+
+#[cfg(feature = "capture")]
+impl DeviceErrors {
+    /// Queues `error` for the next [`capture_device_errors`] run.
+    pub(crate) fn report(&self, error: String) {
+        if let Ok(mut reported) = self.errors.lock() {
+            reported.push(error);
+        }
+    }
+}
+
+/// Replaces wgpu's uncaptured error handler once the [`RenderDevice`] exists.
+///
+/// wgpu panics on these errors by default, which would end the application
+/// before the broken frame could be captured.
+#[cfg(feature = "capture")]
+pub(crate) fn hook_device_errors(
+    settings: Res<RenderDocSettings>,
+    device: Option<Res<RenderDevice>>,
+    mut errors: ResMut<DeviceErrors>,
+) {
+    if errors.hooked || settings.capture_on_device_error.is_none() {
+        return;
+    }
+
+    if let Some(device) = device {
+        let reported = errors.errors.clone();
+        device.wgpu_device().on_uncaptured_error(move |error| {
+            if let Ok(mut reported) = reported.lock() {
+                reported.push(error.to_string());
+            }
+        });
+        errors.hooked = true;
+    }
+}
+
+/// Logs device errors and captures the following frame for errors not
+/// captured before, unless the cooldown or the per-session cap prevents it.
+#[cfg(feature = "capture")]
+pub(crate) fn capture_device_errors(
+    settings: Res<RenderDocSettings>,
+    time: Option<Res<Time>>,
+    mut errors: ResMut<DeviceErrors>,
+    mut requests: EventWriter<CaptureRequest>,
+) {
+    let reported = match errors.errors.lock() {
+        Ok(mut reported) => std::mem::take(&mut *reported),
+        Err(_) => return,
+    };
+    for error in &reported {
+        error!("wgpu error: {}", error);
+    }
+
+    let (config, time) = match (&settings.capture_on_device_error, time) {
+        (Some(config), Some(time)) => (config, time),
+        _ => return,
+    };
+    let now = time.raw_elapsed();
+    if !errors.budget.allows(now, config.cooldown, config.max_captures) {
+        return;
+    }
+
+    let mut new_errors = Vec::new();
+    for error in reported {
+        if !errors.captured.contains(&error) && !new_errors.contains(&error) {
+            new_errors.push(error);
+        }
+    }
+    if new_errors.is_empty() {
+        return;
+    }
+
+    errors.budget.record(now);
+    let comment = new_errors.join("\n\n");
+    errors.captured.extend(new_errors);
+    requests.send(CaptureRequest {
+        title: Some("wgpu device error".into()),
+        comment: Some(comment),
+        ..default()
+    });
+}
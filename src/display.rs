@@ -0,0 +1,98 @@
+#[cfg(feature = "capture")]
+use bevy::prelude::*;
+use serde::Serialize;
+
+/// Selects winit's backend on Linux, `x11` or `wayland`.
+#[cfg(feature = "capture")]
+const BACKEND_VAR: &str = "WINIT_UNIX_BACKEND";
+
+/// The display server windows are created on, and what the plugin did about
+/// it. Only detected on Linux and the BSDs, see [`RenderDocStatus::display`](crate::RenderDocStatus::display).
+///
+/// RenderDoc cannot capture native Wayland windows. Bevy only uses Wayland
+/// when its `wayland` feature is enabled, otherwise windows go through
+/// XWayland on Wayland sessions.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DisplayInfo {
+    /// Whether `WAYLAND_DISPLAY` is set, so the session runs a Wayland compositor.
+    pub wayland_session: bool,
+    /// Whether `DISPLAY` is set, so X11 or XWayland is available.
+    pub x11_available: bool,
+    /// The value of `WINIT_UNIX_BACKEND`, after the plugin's decision.
+    pub winit_backend: Option<String>,
+    /// Whether the plugin set `WINIT_UNIX_BACKEND=x11`, following
+    /// [`RenderDocSettings::force_x11`](crate::RenderDocSettings::force_x11).
+    pub forced_x11: bool,
+}
+
+impl DisplayInfo {
+    /// Returns whether windows may be created on Wayland, where RenderDoc
+    /// cannot capture them.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use bevy_renderdoc::*;
+    /// #
+    /// let mut display = DisplayInfo {
+    ///     wayland_session: true,
+    ///     x11_available: true,
+    ///     ..Default::default()
+    /// };
+    /// assert!(display.may_use_wayland());
+    ///
+    /// display.winit_backend = Some("x11".into());
+    /// assert!(!display.may_use_wayland());
+    /// ```
+    pub fn may_use_wayland(&self) -> bool {
+        self.wayland_session && self.winit_backend.as_deref() != Some("x11")
+    }
+}
+
+/// Reads the display environment, forcing X11 if asked to and possible.
+///
+/// Has to run before `WinitPlugin` creates the event loop to have any effect.
+#[cfg(feature = "capture")]
+pub(crate) fn configure(app: &mut App, force_x11: bool) -> Option<DisplayInfo> {
+    if cfg!(not(all(unix, not(any(target_os = "macos", target_os = "ios", target_os = "android"))))) {
+        return None;
+    }
+
+    let mut display = DisplayInfo {
+        wayland_session: std::env::var_os("WAYLAND_DISPLAY").is_some(),
+        x11_available: std::env::var_os("DISPLAY").is_some(),
+        winit_backend: std::env::var(BACKEND_VAR).ok(),
+        forced_x11: false,
+    };
+
+    if !display.may_use_wayland() {
+        return Some(display);
+    }
+
+    let message = if force_x11 && display.winit_backend.is_none() && display.x11_available {
+        // Windows already exist once the event loop was created, setting the variable is too late.
+        if app.world.contains_resource::<Windows>() {
+            format!(
+                "RenderDocSettings::force_x11 has no effect, RenderDocPlugin was added after WindowPlugin. \
+                 Add it first, or use `DefaultPlugins.build().with_renderdoc()`, or set {}=x11",
+                BACKEND_VAR
+            )
+        } else {
+            std::env::set_var(BACKEND_VAR, "x11");
+            display.winit_backend = Some("x11".to_owned());
+            display.forced_x11 = true;
+            app.add_startup_system(|| info!("Wayland session detected, using X11 through XWayland so RenderDoc can capture windows"));
+            return Some(display);
+        }
+    } else if force_x11 && display.winit_backend.is_none() {
+        "RenderDocSettings::force_x11 has no effect, DISPLAY is not set. Is XWayland running?".to_owned()
+    } else {
+        format!(
+            "Wayland session detected. RenderDoc cannot capture Wayland windows, which bevy creates when its \
+             `wayland` feature is enabled. Set {}=x11 or RenderDocSettings::force_x11 if captures come back empty",
+            BACKEND_VAR
+        )
+    };
+
+    app.add_startup_system(move || warn!("{}", message));
+    Some(display)
+}
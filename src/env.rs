@@ -0,0 +1,128 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::{CaptureOptions, RenderDocSettings};
+
+/// Set to `0`, `false` or `off` to disable the plugin.
+const ENABLE_VAR: &str = "BEVY_RENDERDOC";
+/// Comma-separated frame indices to capture.
+const CAPTURE_FRAMES_VAR: &str = "BEVY_RENDERDOC_CAPTURE_FRAMES";
+/// Directory captures are written to.
+const DIR_VAR: &str = "BEVY_RENDERDOC_DIR";
+/// Name of a [`CaptureOptions`] preset.
+const CAPTURE_OPTIONS_VAR: &str = "BEVY_RENDERDOC_CAPTURE_OPTIONS";
+/// Command-line equivalent of [`CAPTURE_FRAMES_VAR`], may be repeated.
+const CAPTURE_FRAME_ARG: &str = "--renderdoc-capture-frame";
+
+/// Returns `false` if the plugin was disabled through the environment.
+pub(crate) fn is_enabled() -> bool {
+    match std::env::var(ENABLE_VAR) {
+        Ok(value) => !matches!(value.trim().to_ascii_lowercase().as_str(), "0" | "false" | "off"),
+        Err(_) => true,
+    }
+}
+
+/// Settings overridden from outside the application.
+#[derive(Default)]
+pub(crate) struct Overrides {
+    capture_frames: Vec<u64>,
+    dir: Option<PathBuf>,
+    capture_options: Option<CaptureOptions>,
+    /// Values that could not be parsed, to be logged once logging is up.
+    pub(crate) errors: Vec<String>,
+}
+
+impl Overrides {
+    /// Reads the overrides from the process environment and arguments.
+    pub(crate) fn read() -> Self {
+        Self::read_from(|name| std::env::var_os(name), std::env::args_os().skip(1))
+    }
+
+    /// Reads the overrides from environment variables looked up through `var`,
+    /// and command-line arguments without the program name.
+    pub(crate) fn read_from(
+        var: impl Fn(&str) -> Option<OsString>,
+        args: impl IntoIterator<Item = OsString>,
+    ) -> Self {
+        let mut overrides = Overrides {
+            dir: var(DIR_VAR).map(PathBuf::from),
+            ..Default::default()
+        };
+
+        if let Some(preset) = var(CAPTURE_OPTIONS_VAR).and_then(|preset| preset.into_string().ok()) {
+            match preset.parse() {
+                Ok(options) => overrides.capture_options = Some(options),
+                Err(e) => overrides.errors.push(format!("Ignoring {}: {}", CAPTURE_OPTIONS_VAR, e)),
+            }
+        }
+
+        if let Some(frames) = var(CAPTURE_FRAMES_VAR).and_then(|frames| frames.into_string().ok()) {
+            overrides.parse_frames(CAPTURE_FRAMES_VAR, &frames);
+        }
+
+        // Arguments that aren't valid Unicode can't be ours, but are valid on Unix.
+        let mut args = args.into_iter().map(|arg| arg.into_string().ok());
+        while let Some(arg) = args.next() {
+            let arg = match arg {
+                Some(arg) => arg,
+                None => continue,
+            };
+            if arg == CAPTURE_FRAME_ARG {
+                match args.next().flatten() {
+                    Some(frames) => overrides.parse_frames(CAPTURE_FRAME_ARG, &frames),
+                    None => overrides
+                        .errors
+                        .push(format!("{} expects a frame number", CAPTURE_FRAME_ARG)),
+                }
+            } else if let Some(frames) = arg.strip_prefix(CAPTURE_FRAME_ARG).and_then(|a| a.strip_prefix('=')) {
+                overrides.parse_frames(CAPTURE_FRAME_ARG, frames);
+            }
+        }
+
+        overrides
+    }
+
+    /// Applies the overrides on top of the app's [`RenderDocSettings`], and
+    /// warns about values that could not be parsed once logging is up.
+    pub(crate) fn insert(self, app: &mut App) {
+        let mut settings = app
+            .world
+            .get_resource::<RenderDocSettings>()
+            .cloned()
+            .unwrap_or_default();
+        self.apply(&mut settings);
+        app.insert_resource(settings);
+
+        if !self.errors.is_empty() {
+            let errors = self.errors;
+            app.add_startup_system(move || errors.iter().for_each(|e| warn!("{}", e)));
+        }
+    }
+
+    /// Applies the overrides on top of `settings`.
+    fn apply(&self, settings: &mut RenderDocSettings) {
+        if let Some(dir) = &self.dir {
+            settings.capture_path_template = dir.join("bevy_capture");
+        }
+        if let Some(options) = &self.capture_options {
+            settings.capture_options = options.clone();
+        }
+
+        settings.capture_at_frames.extend(&self.capture_frames);
+        settings.capture_at_frames.sort_unstable();
+        settings.capture_at_frames.dedup();
+    }
+
+    fn parse_frames(&mut self, source: &str, frames: &str) {
+        for frame in frames.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            match frame.parse() {
+                Ok(frame) => self.capture_frames.push(frame),
+                Err(_) => self
+                    .errors
+                    .push(format!("Ignoring invalid frame number \"{}\" in {}", frame, source)),
+            }
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use bevy::{app::PluginGroupBuilder, render::RenderPlugin, window::WindowPlugin};
+
+use crate::{RenderDocPlugin, RenderDocRenderPlugin};
+
+/// Adds the RenderDoc plugins to a plugin group, in the order they require.
+pub trait RenderDocPluginGroupExt {
+    /// Inserts [`RenderDocPlugin`] before the [`WindowPlugin`], so it can
+    /// choose the windowing backend, and [`RenderDocRenderPlugin`] after the
+    /// [`RenderPlugin`].
+    ///
+    /// # Panics
+    /// Panics if the group does not contain the [`WindowPlugin`] and the [`RenderPlugin`].
+    ///
+    /// # Examples
+    /// ```rust, no_run
+    /// # use bevy::prelude::*;
+    /// # use bevy_renderdoc::*;
+    /// #
+    /// App::new()
+    ///     .add_plugins(DefaultPlugins.build().with_renderdoc())
+    ///     .run();
+    /// ```
+    fn with_renderdoc(self) -> Self;
+}
+
+impl RenderDocPluginGroupExt for PluginGroupBuilder {
+    fn with_renderdoc(self) -> Self {
+        self.add_before::<WindowPlugin, _>(RenderDocPlugin)
+            .add_after::<RenderPlugin, _>(RenderDocRenderPlugin)
+    }
+}
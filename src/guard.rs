@@ -0,0 +1,346 @@
+use std::time::Duration;
+#[cfg(feature = "capture")]
+use std::time::Instant;
+#[cfg(not(feature = "capture"))]
+use std::marker::PhantomData;
+#[cfg(feature = "capture")]
+use std::ptr;
+
+use bevy::prelude::*;
+
+use crate::CaptureRequest;
+#[cfg(feature = "capture")]
+use crate::capture::{wildcard_device, CaptureTracker};
+#[cfg(feature = "capture")]
+use crate::{CaptureBackend, RenderDocApi, RenderDocSettings};
+#[cfg(not(feature = "capture"))]
+use crate::RenderDocResource;
+
+/// Limits how long a [`CaptureScope`] may keep its capture open before the
+/// plugin ends it.
+///
+/// A scope that is leaked keeps recording, growing RenderDoc's memory use
+/// every frame. A capture left open past either limit is discarded, or ended
+/// if discarding isn't supported. Captures the plugin requests end on their
+/// own and are not limited, nor are captures started directly through
+/// [`RenderDocResource`](crate::RenderDocResource).
+///
+/// Set through [`RenderDocSettings::open_capture_limit`](crate::RenderDocSettings::open_capture_limit).
+///
+/// # Examples
+#[cfg_attr(feature = "capture", doc = "```rust")]
+#[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let mut app = App::new();
+/// app.insert_resource(RenderDocSettings {
+///     open_capture_limit: Some(OpenCaptureLimit {
+///         frames: Some(3),
+///         ..default()
+///     }),
+///     ..default()
+/// })
+/// .add_plugins(MinimalPlugins)
+/// .add_plugin(MockRenderDocPlugin::default());
+///
+/// // A capture that is never ended.
+/// let mut mock = app.world.non_send_resource_mut::<MockBackend>();
+/// std::mem::forget(CaptureScope::begin(&mut *mock));
+///
+/// for _ in 0..5 {
+///     app.update();
+/// }
+///
+/// let mock = app.world.non_send_resource::<MockBackend>();
+/// assert_eq!(mock.frames_of(&BackendCall::DiscardFrameCapture), vec![3]);
+/// assert!(!mock.is_frame_capturing());
+/// assert_eq!(mock.get_num_captures(), 0);
+///
+/// // Requested captures run as long as they were asked to.
+/// app.world.send_event(CaptureRequest::span(6));
+/// for _ in 0..7 {
+///     app.update();
+/// }
+///
+/// let mock = app.world.non_send_resource::<MockBackend>();
+/// assert_eq!(mock.frames_of(&BackendCall::DiscardFrameCapture), vec![3]);
+/// assert_eq!(mock.get_num_captures(), 1);
+/// ```
+///
+/// Each backend tracks its own scopes, so apps don't affect each other's limits:
+#[cfg_attr(feature = "capture", doc = "```rust")]
+#[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let limited_app = || {
+///     let mut app = App::new();
+///     app.insert_resource(RenderDocSettings {
+///         open_capture_limit: Some(OpenCaptureLimit {
+///             frames: Some(3),
+///             ..default()
+///         }),
+///         ..default()
+///     })
+///     .add_plugins(MinimalPlugins)
+///     .add_plugin(MockRenderDocPlugin::default());
+///     app
+/// };
+/// let (mut leaking, mut other) = (limited_app(), limited_app());
+///
+/// let mut mock = leaking.world.non_send_resource_mut::<MockBackend>();
+/// std::mem::forget(CaptureScope::begin(&mut *mock));
+///
+/// for _ in 0..5 {
+///     other.update();
+///     leaking.update();
+/// }
+///
+/// let mock = leaking.world.non_send_resource::<MockBackend>();
+/// assert_eq!(mock.frames_of(&BackendCall::DiscardFrameCapture), vec![3]);
+/// let mock = other.world.non_send_resource::<MockBackend>();
+/// assert!(mock.frames_of(&BackendCall::DiscardFrameCapture).is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct OpenCaptureLimit {
+    /// The number of frames a capture may stay open. Defaults to `600`.
+    pub frames: Option<u32>,
+    /// How long a capture may stay open. Defaults to 30 seconds.
+    pub duration: Option<Duration>,
+    /// Discards the capture instead of writing it to disk, if the loaded
+    /// RenderDoc API supports it. Defaults to `true`.
+    pub discard: bool,
+}
+
+impl Default for OpenCaptureLimit {
+    fn default() -> Self {
+        Self {
+            frames: Some(600),
+            duration: Some(Duration::from_secs(30)),
+            discard: true,
+        }
+    }
+}
+
+/// Captures the GPU work submitted while it is alive, ending the capture when dropped.
+///
+/// Useful for work submitted outside the render graph, such as compute passes
+/// dispatched through the `RenderQueue` from a system. Captures of the render
+/// graph are requested with [`CaptureRequest`]s instead.
+///
+/// A scope that is leaked, or otherwise never dropped, is ended after
+/// [`RenderDocSettings::open_capture_limit`](crate::RenderDocSettings::open_capture_limit).
+///
+/// # Examples
+#[cfg_attr(feature = "capture", doc = "```rust")]
+#[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let mut mock = MockBackend::default();
+/// {
+///     let _scope = CaptureScope::begin(&mut mock).unwrap();
+///     // Submit work here.
+/// }
+/// assert_eq!(mock.frames_of(&BackendCall::EndFrameCapture), vec![0]);
+/// assert_eq!(mock.get_num_captures(), 1);
+/// ```
+///
+/// In a system:
+/// ```rust, no_run
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// fn dispatch(mut rd: Option<NonSendMut<RenderDocResource>>) {
+///     let _scope = rd.as_deref_mut().and_then(CaptureScope::begin);
+///     // Submit work here.
+/// }
+/// ```
+#[cfg(feature = "capture")]
+#[must_use = "the capture ends when the scope is dropped"]
+pub struct CaptureScope<'a, B: CaptureBackend = RenderDocApi> {
+    rd: &'a mut B,
+    open: bool,
+}
+
+#[cfg(feature = "capture")]
+impl<'a, B: CaptureBackend> CaptureScope<'a, B> {
+    /// Starts capturing on every device and window.
+    ///
+    /// Returns [`None`] if a frame capture is already in progress, since
+    /// RenderDoc cannot nest them.
+    pub fn begin(rd: &'a mut B) -> Option<Self> {
+        if rd.is_frame_capturing() {
+            return None;
+        }
+        rd.start_frame_capture(wildcard_device(), ptr::null());
+        rd.set_scope_started(Some(Instant::now()));
+        Some(Self { rd, open: true })
+    }
+
+    /// Ends the capture and saves it to disk, like dropping the scope.
+    pub fn end(mut self) {
+        self.close(false);
+    }
+
+    /// Ends the capture without saving it.
+    ///
+    /// Returns `false` if the loaded RenderDoc API cannot discard captures, in
+    /// which case the capture is saved instead.
+    pub fn discard(mut self) -> bool {
+        self.close(true)
+    }
+
+    fn close(&mut self, discard: bool) -> bool {
+        if !std::mem::take(&mut self.open) {
+            return false;
+        }
+        self.rd.set_scope_started(None);
+        if discard && self.rd.discard_frame_capture(wildcard_device(), ptr::null()) {
+            return true;
+        }
+        self.rd.end_frame_capture(wildcard_device(), ptr::null());
+        false
+    }
+}
+
+#[cfg(feature = "capture")]
+impl<B: CaptureBackend> Drop for CaptureScope<'_, B> {
+    fn drop(&mut self) {
+        self.close(false);
+    }
+}
+
+/// Without RenderDoc, a [`RenderDocResource`] never exists to begin a scope with.
+#[cfg(not(feature = "capture"))]
+#[must_use = "the capture ends when the scope is dropped"]
+pub struct CaptureScope<'a>(PhantomData<&'a mut RenderDocResource>);
+
+#[cfg(not(feature = "capture"))]
+impl<'a> CaptureScope<'a> {
+    /// Starts capturing on every device and window.
+    pub fn begin(rd: &'a mut RenderDocResource) -> Option<Self> {
+        match *rd {}
+    }
+
+    /// Ends the capture and saves it to disk, like dropping the scope.
+    pub fn end(self) {}
+
+    /// Ends the capture without saving it.
+    pub fn discard(self) -> bool {
+        false
+    }
+}
+
+/// Requests captures through [`Commands`].
+pub trait RenderDocCommandsExt {
+    /// Captures the next `frames` frames into a single capture file, ending
+    /// the capture after the last one. Shorthand for sending
+    /// [`CaptureRequest::span`].
+    ///
+    /// Like other commands, the request is sent once the stage ends, so a
+    /// capture requested from an `Update` system starts in the next frame.
+    ///
+    /// # Examples
+    #[cfg_attr(feature = "capture", doc = "```rust")]
+    #[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+    /// # use bevy::prelude::*;
+    /// # use bevy_renderdoc::testing::*;
+    /// # use bevy_renderdoc::*;
+    /// #
+    /// fn capture_loading(mut commands: Commands) {
+    ///     commands.capture_frames(2);
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.add_plugins(MinimalPlugins)
+    ///     .add_plugin(MockRenderDocPlugin::default())
+    ///     .add_startup_system(capture_loading);
+    /// for _ in 0..4 {
+    ///     app.update();
+    /// }
+    ///
+    /// let mock = app.world.non_send_resource::<MockBackend>();
+    /// assert_eq!(mock.frames_of(&BackendCall::StartFrameCapture), vec![0]);
+    /// assert_eq!(mock.frames_of(&BackendCall::EndFrameCapture), vec![2]);
+    /// ```
+    fn capture_frames(&mut self, frames: u32);
+}
+
+impl RenderDocCommandsExt for Commands<'_, '_> {
+    fn capture_frames(&mut self, frames: u32) {
+        self.add(move |world: &mut World| {
+            // Missing if the plugin wasn't added.
+            if let Some(mut requests) = world.get_resource_mut::<Events<CaptureRequest>>() {
+                requests.send(CaptureRequest::span(frames));
+            }
+        });
+    }
+}
+
+/// When the frame capture the plugin found a [`CaptureScope`] had begun, and
+/// the frame it was first seen on.
+#[cfg(feature = "capture")]
+#[derive(Resource, Default)]
+pub(crate) struct OpenCapture(Option<(Instant, u64)>);
+
+/// Ends captures a [`CaptureScope`] left open for longer than
+/// [`RenderDocSettings::open_capture_limit`].
+///
+/// Captures RenderDoc triggered itself are open between presents, so only
+/// scopes are limited.
+#[cfg(feature = "capture")]
+pub(crate) fn limit_open_captures<B: CaptureBackend>(
+    mut rd: NonSendMut<B>,
+    settings: Res<RenderDocSettings>,
+    tracker: Res<CaptureTracker>,
+    mut open: ResMut<OpenCapture>,
+) {
+    let began = match rd.scope_started() {
+        Some(began) if rd.is_frame_capturing() => began,
+        _ => {
+            // Also forgets scopes whose capture was ended through RenderDocResource.
+            rd.set_scope_started(None);
+            open.0 = None;
+            return;
+        }
+    };
+
+    let since_frame = match open.0 {
+        Some((seen, frame)) if seen == began => frame,
+        _ => {
+            open.0 = Some((began, tracker.frame()));
+            tracker.frame()
+        }
+    };
+    let limit = match &settings.open_capture_limit {
+        Some(limit) => limit,
+        None => return,
+    };
+
+    let frames = tracker.frame() - since_frame;
+    let elapsed = began.elapsed();
+    let exceeded = limit.frames.is_some_and(|max| frames >= max as u64)
+        || limit.duration.is_some_and(|max| elapsed >= max);
+    if !exceeded {
+        return;
+    }
+
+    rd.set_scope_started(None);
+    open.0 = None;
+    if limit.discard && rd.discard_frame_capture(wildcard_device(), ptr::null()) {
+        warn!(
+            "Discarded a RenderDoc capture left open by a CaptureScope for {} frames ({:.1?}). Was it leaked?",
+            frames, elapsed
+        );
+    } else {
+        rd.end_frame_capture(wildcard_device(), ptr::null());
+        warn!(
+            "Ended a RenderDoc capture left open by a CaptureScope for {} frames ({:.1?}). Was it leaked?",
+            frames, elapsed
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+#[cfg(feature = "capture")]
+use renderdoc::InputButton;
+
+/// The physical or logical key a [`KeyBinding`] listens to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// A layout-dependent [`KeyCode`].
+    Code(KeyCode),
+    /// A raw [`ScanCode`], useful for non-QWERTY layouts where the
+    /// [`KeyCode`] of a physical key differs.
+    Scan(ScanCode),
+}
+
+impl From<KeyCode> for Key {
+    fn from(code: KeyCode) -> Self {
+        Key::Code(code)
+    }
+}
+
+impl From<ScanCode> for Key {
+    fn from(code: ScanCode) -> Self {
+        Key::Scan(code)
+    }
+}
+
+/// A key, optionally combined with modifier keys, that performs a RenderDoc action.
+///
+/// Modifiers match either side of the keyboard, so [`KeyCode::LControl`] also
+/// accepts [`KeyCode::RControl`].
+///
+/// Bindings without modifiers whose key has a RenderDoc equivalent are also
+/// registered with RenderDoc itself, so they keep working when RenderDoc is
+/// driving the capture. Any other binding is handled from bevy only.
+///
+/// # Examples
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let plain = KeyBinding::from(KeyCode::F12);
+/// let chord = KeyBinding::new(KeyCode::C).with_modifier(KeyCode::LControl);
+/// let scan = KeyBinding::new(ScanCode(88));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    /// The key that activates the binding.
+    pub key: Key,
+    /// Keys that must be held down while `key` is pressed.
+    pub modifiers: Vec<KeyCode>,
+}
+
+impl KeyBinding {
+    /// Creates a binding for `key` without any modifiers.
+    pub fn new(key: impl Into<Key>) -> Self {
+        Self {
+            key: key.into(),
+            modifiers: Vec::new(),
+        }
+    }
+
+    /// Adds a modifier that must be held for the binding to activate.
+    pub fn with_modifier(mut self, modifier: KeyCode) -> Self {
+        self.modifiers.push(modifier);
+        self
+    }
+
+    /// Returns the RenderDoc equivalent of this binding, if RenderDoc can
+    /// represent it.
+    #[cfg(feature = "capture")]
+    pub(crate) fn input_button(&self) -> Option<InputButton> {
+        match self.key {
+            Key::Code(code) if self.modifiers.is_empty() => input_button(code),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the binding was activated this frame.
+    pub fn just_pressed(&self, keys: &Input<KeyCode>, scan_codes: &Input<ScanCode>) -> bool {
+        let key_pressed = match self.key {
+            Key::Code(code) => keys.just_pressed(code),
+            Key::Scan(code) => scan_codes.just_pressed(code),
+        };
+
+        key_pressed
+            && self
+                .modifiers
+                .iter()
+                .all(|&modifier| keys.any_pressed([modifier, other_side(modifier)]))
+    }
+}
+
+impl From<KeyCode> for KeyBinding {
+    fn from(code: KeyCode) -> Self {
+        Self::new(code)
+    }
+}
+
+impl From<ScanCode> for KeyBinding {
+    fn from(code: ScanCode) -> Self {
+        Self::new(code)
+    }
+}
+
+fn other_side(modifier: KeyCode) -> KeyCode {
+    match modifier {
+        KeyCode::LControl => KeyCode::RControl,
+        KeyCode::RControl => KeyCode::LControl,
+        KeyCode::LShift => KeyCode::RShift,
+        KeyCode::RShift => KeyCode::LShift,
+        KeyCode::LAlt => KeyCode::RAlt,
+        KeyCode::RAlt => KeyCode::LAlt,
+        KeyCode::LWin => KeyCode::RWin,
+        KeyCode::RWin => KeyCode::LWin,
+        other => other,
+    }
+}
+
+/// Maps a bevy [`KeyCode`] to the matching RenderDoc [`InputButton`].
+///
+/// Returns [`None`] for keys RenderDoc cannot listen to.
+#[cfg(feature = "capture")]
+pub(crate) fn input_button(code: KeyCode) -> Option<InputButton> {
+    let button = match code {
+        KeyCode::Key0 => InputButton::Key0,
+        KeyCode::Key1 => InputButton::Key1,
+        KeyCode::Key2 => InputButton::Key2,
+        KeyCode::Key3 => InputButton::Key3,
+        KeyCode::Key4 => InputButton::Key4,
+        KeyCode::Key5 => InputButton::Key5,
+        KeyCode::Key6 => InputButton::Key6,
+        KeyCode::Key7 => InputButton::Key7,
+        KeyCode::Key8 => InputButton::Key8,
+        KeyCode::Key9 => InputButton::Key9,
+        KeyCode::A => InputButton::A,
+        KeyCode::B => InputButton::B,
+        KeyCode::C => InputButton::C,
+        KeyCode::D => InputButton::D,
+        KeyCode::E => InputButton::E,
+        KeyCode::F => InputButton::F,
+        KeyCode::G => InputButton::G,
+        KeyCode::H => InputButton::H,
+        KeyCode::I => InputButton::I,
+        KeyCode::J => InputButton::J,
+        KeyCode::K => InputButton::K,
+        KeyCode::L => InputButton::L,
+        KeyCode::M => InputButton::M,
+        KeyCode::N => InputButton::N,
+        KeyCode::O => InputButton::O,
+        KeyCode::P => InputButton::P,
+        KeyCode::Q => InputButton::Q,
+        KeyCode::R => InputButton::R,
+        KeyCode::S => InputButton::S,
+        KeyCode::T => InputButton::T,
+        KeyCode::U => InputButton::U,
+        KeyCode::V => InputButton::V,
+        KeyCode::W => InputButton::W,
+        KeyCode::X => InputButton::X,
+        KeyCode::Y => InputButton::Y,
+        KeyCode::Z => InputButton::Z,
+        KeyCode::NumpadDivide => InputButton::Divide,
+        KeyCode::NumpadMultiply => InputButton::Multiply,
+        KeyCode::NumpadSubtract => InputButton::Subtract,
+        KeyCode::NumpadAdd => InputButton::Plus,
+        KeyCode::F1 => InputButton::F1,
+        KeyCode::F2 => InputButton::F2,
+        KeyCode::F3 => InputButton::F3,
+        KeyCode::F4 => InputButton::F4,
+        KeyCode::F5 => InputButton::F5,
+        KeyCode::F6 => InputButton::F6,
+        KeyCode::F7 => InputButton::F7,
+        KeyCode::F8 => InputButton::F8,
+        KeyCode::F9 => InputButton::F9,
+        KeyCode::F10 => InputButton::F10,
+        KeyCode::F11 => InputButton::F11,
+        KeyCode::F12 => InputButton::F12,
+        KeyCode::Home => InputButton::Home,
+        KeyCode::End => InputButton::End,
+        KeyCode::Insert => InputButton::Insert,
+        KeyCode::Delete => InputButton::Delete,
+        KeyCode::PageUp => InputButton::PageUp,
+        KeyCode::PageDown => InputButton::PageDn,
+        KeyCode::Back => InputButton::Backspace,
+        KeyCode::Tab => InputButton::Tab,
+        KeyCode::Snapshot => InputButton::PrtScrn,
+        KeyCode::Pause => InputButton::Pause,
+        _ => return None,
+    };
+    Some(button)
+}
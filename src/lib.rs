@@ -5,7 +5,21 @@
 //! Allows the user to launch the RenderDoc UI on capture, which makes
 //! taking captures more convenient.
 //!
-//! Default hotkey for taking a capture is `F12`.
+//! Default hotkey for taking a capture is `F12`. Hotkeys can be changed
+//! through the [`RenderDocSettings`] resource. Captures can also be requested
+//! from any system by sending a [`CaptureRequest`] event.
+//!
+//! # Features
+//! - `capture` (default): loads RenderDoc in builds with debug assertions.
+//!   In release builds the plugins keep their API, and [`testing`] still
+//!   works, but RenderDoc is never loaded.
+//!
+//!   Without it, the plugins, events, settings, [`RenderDocController`],
+//!   [`CaptureScope`] and [`RenderDocResource`] keep their API but do nothing,
+//!   and the `renderdoc` and `sysinfo` dependencies are not used.
+//!   [`RenderDocResource`] is never inserted, and items that expose RenderDoc's
+//!   own types, such as `testing` and `CaptureBackend`, are left out.
+//! - `release`: also loads RenderDoc in builds without debug assertions.
 //!
 //! # Examples
 //!
@@ -16,19 +30,87 @@
 //! App::new()
 //!     .add_plugin(RenderDocPlugin) // order is important
 //!     .add_plugins(DefaultPlugins)
+//!     .add_plugin(RenderDocRenderPlugin)
 //!     .run();
 //!
-use bevy::{prelude::*, render::renderer::RenderDevice};
+#[cfg(feature = "capture")]
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+#[cfg(feature = "capture")]
+use bevy::render::{renderer::RenderDevice, RenderStage};
+use bevy::render::RenderApp;
+#[cfg(feature = "capture")]
 use renderdoc::*;
-use sysinfo::{Pid, ProcessRefreshKind, SystemExt};
 
+#[cfg(feature = "capture")]
+mod api;
+#[cfg(feature = "capture")]
+mod backend;
+mod capture;
+mod controller;
+#[cfg(feature = "capture")]
+mod debug_groups;
+mod device_error;
+mod display;
+#[cfg(feature = "capture")]
+mod env;
+mod group;
+mod guard;
+mod keys;
+mod load;
+mod metadata;
+mod options;
+mod overlay;
+mod panic_capture;
+mod replay;
+mod scope;
+mod settings;
+mod spike;
+mod status;
+#[cfg(feature = "capture")]
+mod shaders;
+#[cfg(feature = "capture")]
+mod window;
+mod wgpu_settings;
+#[cfg(feature = "capture")]
+pub mod testing;
+
+#[cfg(feature = "capture")]
+pub use api::*;
+#[cfg(feature = "capture")]
+pub use backend::*;
+pub use capture::{CaptureCompleted, CaptureMode, CaptureRequest};
+pub use controller::RenderDocController;
+pub use device_error::DeviceErrorCapture;
+pub use display::DisplayInfo;
+pub use group::RenderDocPluginGroupExt;
+pub use guard::{CaptureScope, OpenCaptureLimit, RenderDocCommandsExt};
+pub use keys::*;
+pub use load::{LoadErrorKind, RenderDocLoadError, SearchedPath};
+pub use metadata::{RenderDocAppExt, SIDECAR_SCHEMA_VERSION};
+pub use options::{CaptureOptions, UnknownPreset};
+pub use overlay::RenderDocOverlay;
+pub use panic_capture::PanicCapture;
+pub use replay::ReplayUiPolicy;
+pub use scope::RenderDocCaptureScope;
+#[cfg(feature = "capture")]
 pub use renderdoc;
+pub use settings::*;
+pub use spike::SpikeCapture;
+pub use status::RenderDocStatus;
+pub use wgpu_settings::capture_wgpu_settings;
 
-/// The RenderDoc [`Version`] this plugin uses.
+/// The oldest RenderDoc [`Version`] this plugin supports.
+#[cfg(feature = "capture")]
 pub type RenderDocVersion = V110;
 
 /// The type of the [`NonSend`] resource used to store [`RenderDoc`] in [`bevy`].
 ///
+/// The plugin negotiates the newest API version the loaded library supports.
+/// The resource dereferences to [`RenderDoc<RenderDocVersion>`], and
+/// [`RenderDocCapabilities`] describes which newer features are available.
+///
 /// Since the plugin may fail to initialize, the resource must be accessed via
 /// an [`Option`].
 ///
@@ -49,70 +131,531 @@ pub type RenderDocVersion = V110;
 ///     .add_startup_system(modify_renderdoc)
 ///     .run();
 /// ```
-pub type RenderDocResource = RenderDoc<RenderDocVersion>;
+#[cfg(feature = "capture")]
+pub type RenderDocResource = RenderDocApi;
+
+/// Never inserted in builds without the `capture` feature, so systems taking
+/// an `Option<NonSendMut<RenderDocResource>>` compile and receive [`None`].
+///
+/// Has the methods of RenderDoc's API that game code calls, so that code
+/// compiles unchanged. Parameters of `renderdoc` types are generic, and
+/// methods returning them are left out.
+#[cfg(not(feature = "capture"))]
+pub enum RenderDocResource {}
+
+#[cfg(not(feature = "capture"))]
+#[allow(missing_docs)]
+impl RenderDocResource {
+    pub fn get_api_version(&self) -> (u32, u32, u32) {
+        match *self {}
+    }
+
+    pub fn set_capture_option_f32<O>(&mut self, _opt: O, _val: f32) {
+        match *self {}
+    }
+
+    pub fn set_capture_option_u32<O>(&mut self, _opt: O, _val: u32) {
+        match *self {}
+    }
+
+    pub fn get_capture_option_f32<O>(&self, _opt: O) -> f32 {
+        match *self {}
+    }
+
+    pub fn get_capture_option_u32<O>(&self, _opt: O) -> u32 {
+        match *self {}
+    }
+
+    pub fn set_capture_keys<I: Clone>(&mut self, _keys: &[I]) {
+        match *self {}
+    }
+
+    pub fn set_focus_toggle_keys<I: Clone>(&mut self, _keys: &[I]) {
+        match *self {}
+    }
+
+    pub fn unload_crash_handler(&mut self) {
+        match *self {}
+    }
+
+    pub fn mask_overlay_bits<B>(&mut self, _and: B, _or: B) {
+        match *self {}
+    }
+
+    pub fn get_log_file_path_template(&self) -> &std::path::Path {
+        match *self {}
+    }
+
+    pub fn set_log_file_path_template<P: Into<std::path::PathBuf>>(&mut self, _path_template: P) {
+        match *self {}
+    }
+
+    pub fn get_num_captures(&self) -> u32 {
+        match *self {}
+    }
+
+    pub fn get_capture(&self, _index: u32) -> Option<(std::path::PathBuf, std::time::SystemTime)> {
+        match *self {}
+    }
+
+    pub fn trigger_capture(&mut self) {
+        match *self {}
+    }
+
+    pub fn trigger_multi_frame_capture(&mut self, _num_frames: u32) {
+        match *self {}
+    }
+
+    pub fn is_target_control_connected(&self) -> bool {
+        match *self {}
+    }
+
+    pub fn launch_replay_ui<'a, O: Into<Option<&'a str>>>(
+        &self,
+        _connect_immediately: bool,
+        _extra_opts: O,
+    ) -> Result<u32, std::convert::Infallible> {
+        match *self {}
+    }
+
+    pub fn set_active_window<D>(&mut self, _dev: D, _win: *const std::ffi::c_void) {
+        match *self {}
+    }
+
+    pub fn start_frame_capture<D>(&mut self, _dev: D, _win: *const std::ffi::c_void) {
+        match *self {}
+    }
+
+    pub fn is_frame_capturing(&self) -> bool {
+        match *self {}
+    }
+
+    pub fn end_frame_capture<D>(&mut self, _dev: D, _win: *const std::ffi::c_void) {
+        match *self {}
+    }
+}
+
+/// Labels for the systems added by [`RenderDocPlugin`].
+#[derive(SystemLabel, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RenderDocSystem {
+    /// Applies [`RenderDocSettings`] to RenderDoc when they change.
+    ApplySettings,
+    /// Turns hotkey presses into [`CaptureRequest`]s.
+    Hotkeys,
+    /// Executes commands queued through [`RenderDocController`], before [`Requests`](Self::Requests).
+    Controller,
+    /// Handles [`CaptureRequest`]s sent before this label.
+    Requests,
+    /// Ends [`CaptureMode::Span`] captures, and captures a [`CaptureScope`] left
+    /// open past [`RenderDocSettings::open_capture_limit`]. Runs in [`CoreStage::First`].
+    FrameSpan,
+    /// Sends [`CaptureCompleted`] for new captures, before their comments and
+    /// sidecar files are written. Runs in [`CoreStage::Last`].
+    Completion,
+}
 
 /// A plugin that enables [`RenderDoc`] for this application.
 ///
 /// **This plugin needs to be inserted before the [`RenderPlugin`](bevy::render::RenderPlugin)**!\
 /// Since the [`RenderPlugin`](bevy::render::RenderPlugin) is part of the [`DefaultPlugins`], this
-/// plugin also needs to be added before that. To be safe, just add it first.
+/// plugin also needs to be added before that. To be safe, just add it first, or
+/// use [`with_renderdoc`](RenderDocPluginGroupExt::with_renderdoc), which
+/// inserts it in the right place.
 ///
 /// See [crate documentation](crate) for basic usage.
+///
+/// # Environment
+/// Some settings can be overridden without recompiling:
+/// - `BEVY_RENDERDOC=0` disables the plugin entirely.
+/// - `BEVY_RENDERDOC_CAPTURE_FRAMES=1,120,500`, or one or more
+///   `--renderdoc-capture-frame 120` arguments, capture the given frames.
+/// - `BEVY_RENDERDOC_DIR=captures` writes captures to the given directory.
+/// - `BEVY_RENDERDOC_CAPTURE_OPTIONS=validation` selects a [`CaptureOptions`]
+///   preset: `shader-debugging`, `validation` or `minimal-overhead`.
+/// - `RENDERDOC_LIB_PATH=/opt/renderdoc/lib/librenderdoc.so` loads RenderDoc
+///   from the given file or directory, instead of the library search path.
+///
+/// If RenderDoc fails to load, a [`RenderDocLoadError`] resource describes why.
 pub struct RenderDocPlugin;
+#[cfg(feature = "capture")]
 impl Plugin for RenderDocPlugin {
     fn build(&self, app: &mut App) {
-        let has_invalid_setup = app.world.contains_resource::<RenderDevice>()
-            || app.world.contains_resource::<Windows>();
+        // Builds without debug assertions only load RenderDoc with the `release` feature.
+        if !cfg!(renderdoc_enabled) {
+            add_resources(app);
+            app.insert_resource(RenderDocStatus::failed(
+                "RenderDoc is only loaded in release builds with the `release` feature",
+            ));
+            return;
+        }
 
-        if has_invalid_setup {
-            app.add_startup_system(|| {
-                error!("RenderDocPlugin needs to be added before RenderPlugin!");
-            });
+        // RenderDoc hooks the graphics API when it is loaded, so it misses a
+        // render device that was created before.
+        if app.world.contains_resource::<RenderDevice>() {
+            add_resources(app);
+            app.insert_resource(RenderDocStatus::failed("RenderDocPlugin was added after RenderPlugin"));
+            misordered(
+                app,
+                "RenderDocPlugin needs to be added before RenderPlugin. Add it before DefaultPlugins, \
+                 or add `DefaultPlugins.build().with_renderdoc()` instead of both plugins",
+            );
+            return;
+        }
+
+        if !env::is_enabled() {
+            add_resources(app);
+            app.insert_resource(RenderDocStatus::failed("Disabled through the BEVY_RENDERDOC environment variable"));
+            app.add_startup_system(|| info!("RenderDoc disabled through the BEVY_RENDERDOC environment variable"));
             return;
         }
 
-        match RenderDoc::<RenderDocVersion>::new() {
-            Ok(mut rd) => {
-                rd.set_log_file_path_template("renderdoc/bevy_capture");
-                rd.mask_overlay_bits(OverlayBits::NONE, OverlayBits::NONE);
+        env::Overrides::read().insert(app);
 
-                app.world.insert_non_send_resource(rd);
-                app.add_startup_system(|| info!("Initialized RenderDoc successfully!"));
-                app.add_system(trigger_capture);
+        match load::load() {
+            Ok(rd) => {
+                let capabilities = rd.capabilities();
+                let settings = app.world.resource::<RenderDocSettings>();
+                let (capture_friendly_wgpu, force_x11) = (settings.capture_friendly_wgpu, settings.force_x11);
+                let panic_capture = settings.panic_capture.clone();
+                if capture_friendly_wgpu {
+                    wgpu_settings::configure(app);
+                }
+                let display = display::configure(app, force_x11);
+                build_with_backend(app, rd);
+                app.world.resource_mut::<RenderDocStatus>().display = display;
+                if let Some(panic_capture) = panic_capture {
+                    // The hook gets its own handle, since it may run on any thread.
+                    match RenderDocApi::new() {
+                        Ok(rd) => panic_capture::install(app, rd, panic_capture),
+                        Err(e) => {
+                            app.add_startup_system(move || error!("Failed to install the RenderDoc panic hook: \"{}\"", e));
+                        }
+                    }
+                }
+                app.add_startup_system(move || {
+                    let (major, minor, patch) = capabilities.requested_version;
+                    let (lib_major, lib_minor, lib_patch) = capabilities.api_version;
+                    info!(
+                        "Initialized RenderDoc successfully! Using API {}.{}.{}, library provides {}.{}.{}",
+                        major, minor, patch, lib_major, lib_minor, lib_patch
+                    );
+                });
             }
             Err(e) => {
-                app.add_startup_system(move || error!("Failed to initialize RenderDoc. Ensure RenderDoc is installed and visible from your $PATH. Error: \"{}\"", e));
+                add_resources(app);
+                app.insert_resource(RenderDocStatus::failed(e.to_string()));
+                app.insert_resource((*e).clone());
+                app.add_startup_system(move || error!("Failed to initialize RenderDoc: {}", e));
             }
         }
     }
 }
 
-fn trigger_capture(
-    key: Option<Res<Input<KeyCode>>>,
-    rd: NonSend<RenderDocResource>,
-    mut replay_pid: Local<usize>,
-    mut system: Local<sysinfo::System>,
-) {
-    if key.is_none() {
-        return;
+/// Without RenderDoc, only the resources and events game code may use are added.
+#[cfg(not(feature = "capture"))]
+impl Plugin for RenderDocPlugin {
+    fn build(&self, app: &mut App) {
+        add_resources(app);
+        app.insert_resource(RenderDocStatus::failed("RenderDoc support is not compiled into this build"));
     }
+}
+
+/// Reports a plugin added in the wrong order, as configured by
+/// [`RenderDocSettings::panic_on_misordering`].
+#[cfg(feature = "capture")]
+fn misordered(app: &mut App, message: &'static str) {
+    let panic = app
+        .world
+        .get_resource::<RenderDocSettings>()
+        .is_none_or(|settings| settings.panic_on_misordering);
+    if panic {
+        panic!("{}", message);
+    }
+    app.add_startup_system(move || error!("{}", message));
+}
 
-    // TODO: If a user were to change this hotkey on the RenderDoc instance
-    // this could get mismatched.
-    if key.unwrap().just_pressed(KeyCode::F12) {
-        // Avoid launching multiple instances of the replay ui
-        if system
-            .refresh_process_specifics(Pid::from(*replay_pid as i32), ProcessRefreshKind::new().with_cpu())
-        {
+/// A plugin that adds RenderDoc features living in the render world: debug
+/// groups around every render graph node, [`RenderDocCaptureScope`] nodes, and
+/// the shader, entry point and shader defs of every pipeline in capture sidecars.
+///
+/// **This plugin needs to be inserted after the [`RenderPlugin`](bevy::render::RenderPlugin)**,
+/// and after [`RenderDocPlugin`]. It does nothing if RenderDoc failed to load.
+///
+/// # Examples
+/// ```rust, no_run
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// App::new()
+///     .add_plugin(RenderDocPlugin)
+///     .add_plugins(DefaultPlugins)
+///     .add_plugin(RenderDocRenderPlugin)
+///     .run();
+/// ```
+pub struct RenderDocRenderPlugin;
+#[cfg(feature = "capture")]
+impl Plugin for RenderDocRenderPlugin {
+    fn build(&self, app: &mut App) {
+        insert_render_controller(app);
+
+        // Only present once RenderDoc has been loaded.
+        if !app.world.contains_resource::<RenderDocCapabilities>() {
             return;
         }
 
-        match rd.launch_replay_ui(true, None) {
-            Ok(pid) => {
-                *replay_pid = pid as usize;
-                info!("Launching RenderDoc Replay UI");
+        let settings = app.world.resource::<RenderDocSettings>().clone();
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => {
+                misordered(
+                    app,
+                    "RenderDocRenderPlugin needs to be added after RenderPlugin. Add it after DefaultPlugins, \
+                     or add `DefaultPlugins.build().with_renderdoc()` instead of both plugins",
+                );
+                return;
+            }
+        };
+
+        let pipelines = shaders::ShaderPipelines::default();
+        render_app
+            .insert_resource(pipelines.clone())
+            .add_system_to_stage(RenderStage::Cleanup, shaders::record_pipelines);
+
+        if settings.debug_groups {
+            render_app.add_system_to_stage(RenderStage::Prepare, debug_groups::wrap_render_graph_nodes);
+        }
+
+        // The render world gets its own handle, since RenderDoc isn't thread-safe.
+        match RenderDocApi::new() {
+            Ok(rd) => {
+                render_app
+                    .insert_resource(scope::CaptureScopeState::new(rd))
+                    .add_system_to_stage(RenderStage::Extract, scope::extract_armed_scope);
+                app.init_resource::<scope::ArmedScope>()
+                    .add_system_to_stage(CoreStage::First, scope::count_scope_frames);
             }
-            Err(e) => error!("Failed to launch RenderDoc Replay UI: {}", e),
+            Err(e) => {
+                app.add_startup_system(move || error!("Failed to initialize RenderDoc in the render world: \"{}\"", e));
+            }
+        }
+
+        app.insert_resource(pipelines);
+
+        // wgpu only emits shader debug names when built with debug assertions.
+        if !cfg!(debug_assertions) {
+            app.add_startup_system(|| {
+                warn!(
+                    "Shaders in RenderDoc captures carry no debug names in builds without debug assertions. \
+                     Set `debug-assertions = true` for `wgpu-core` under `[profile.release.package]` to keep them"
+                )
+            });
+        }
+    }
+}
+
+#[cfg(not(feature = "capture"))]
+impl Plugin for RenderDocRenderPlugin {
+    fn build(&self, app: &mut App) {
+        insert_render_controller(app);
+    }
+}
+
+/// Shares the main world's [`RenderDocController`] with the render world, so
+/// render world systems can use it even if RenderDoc isn't loaded.
+fn insert_render_controller(app: &mut App) {
+    let controller = app.world.get_resource::<RenderDocController>().cloned();
+    if let (Some(controller), Ok(render_app)) = (controller, app.get_sub_app_mut(RenderApp)) {
+        render_app.insert_resource(controller);
+    }
+}
+
+/// Registers the plugin's resources and systems, driven by `backend`.
+#[cfg(feature = "capture")]
+pub(crate) fn build_with_backend<B: CaptureBackend>(app: &mut App, mut backend: B) {
+    add_resources(app);
+
+    let settings = app.world.resource::<RenderDocSettings>();
+    let template = settings.capture_path_template.clone();
+    backend.set_capture_file_path_template(&template);
+
+    // Set before the render device is created, since some options only apply then.
+    let options = settings.capture_options.entries();
+    let rejected: Vec<_> = options
+        .iter()
+        .filter(|(option, value)| !backend.set_capture_option(*option, *value))
+        .copied()
+        .collect();
+    app.add_startup_system(move || {
+        if !options.is_empty() {
+            info!("RenderDoc capture options: {:?}", options);
+        }
+        for (option, value) in &rejected {
+            warn!("RenderDoc rejected capture option {:?} = {}", option, value);
+        }
+    });
+
+    let (controller, controller_commands) = RenderDocController::new();
+    app.insert_resource(controller)
+        .insert_resource(controller_commands)
+        .insert_resource(backend.capabilities())
+        .insert_resource(capture::CaptureTracker::new(backend.get_num_captures()))
+        .init_resource::<guard::OpenCapture>()
+        .init_resource::<replay::ReplayUi>()
+        .init_resource::<spike::FrameTimes>()
+        .init_resource::<device_error::DeviceErrors>();
+    app.world.insert_non_send_resource(backend);
+    panic_capture::honor_marker(app, &template);
+
+    app.add_system_to_stage(CoreStage::First, spike::record_frame_times::<B>)
+        .add_system_to_stage(CoreStage::First, device_error::hook_device_errors)
+        .add_system_to_stage(
+            CoreStage::First,
+            capture::count_span_frames::<B>.label(RenderDocSystem::FrameSpan),
+        )
+        .add_system_to_stage(
+            CoreStage::First,
+            guard::limit_open_captures::<B>
+                .label(RenderDocSystem::FrameSpan)
+                .after(capture::count_span_frames::<B>),
+        )
+        .add_system_to_stage(
+            CoreStage::First,
+            status::update_status::<B>.after(RenderDocSystem::FrameSpan),
+        );
+
+    app.add_system(apply_settings::<B>.label(RenderDocSystem::ApplySettings))
+        .add_system(overlay::apply_overlay::<B>.label(RenderDocSystem::ApplySettings))
+        .add_system(window::activate_focused_window::<B>.before(RenderDocSystem::Requests))
+        .add_system(
+            handle_hotkeys
+                .label(RenderDocSystem::Hotkeys)
+                .after(RenderDocSystem::ApplySettings),
+        )
+        .add_system(capture::capture_scheduled_frames.before(RenderDocSystem::Requests))
+        .add_system(spike::capture_spikes.before(RenderDocSystem::Requests))
+        .add_system(device_error::capture_device_errors.before(RenderDocSystem::Requests))
+        .add_system(
+            controller::execute_controller_commands::<B>
+                .label(RenderDocSystem::Controller)
+                .before(RenderDocSystem::Requests),
+        )
+        .add_system(
+            capture::request_captures::<B>
+                .label(RenderDocSystem::Requests)
+                .after(RenderDocSystem::Hotkeys),
+        )
+        .add_system(capture::title_captures::<B>.after(RenderDocSystem::Requests));
+
+    app.add_system_to_stage(
+        CoreStage::Last,
+        capture::complete_captures::<B>.label(RenderDocSystem::Completion),
+    )
+    .add_system_to_stage(
+        CoreStage::Last,
+        metadata::annotate_captures::<B>.after(RenderDocSystem::Completion),
+    )
+    .add_system_to_stage(CoreStage::Last, replay::close_replay_ui_on_exit);
+}
+
+/// Registers the resources and events that exist even if RenderDoc failed to load,
+/// so systems using them keep working.
+fn add_resources(app: &mut App) {
+    // Without a backend nothing receives the controller's commands, so they are dropped.
+    let (controller, _) = RenderDocController::new();
+    app.insert_resource(controller)
+        .init_resource::<RenderDocSettings>()
+        .init_resource::<RenderDocStatus>()
+        .init_resource::<RenderDocOverlay>()
+        .register_type::<RenderDocOverlay>()
+        .add_event::<CaptureRequest>()
+        .add_event::<CaptureCompleted>();
+}
+
+#[cfg(feature = "capture")]
+fn apply_settings<B: CaptureBackend>(
+    settings: Res<RenderDocSettings>,
+    mut rd: NonSendMut<B>,
+    mut template: Local<Option<PathBuf>>,
+    mut capture_options: Local<Option<CaptureOptions>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    // The template was applied on build. Only touch it when the settings change it,
+    // so templates set directly on the resource survive.
+    let template = template.get_or_insert_with(|| settings.capture_path_template.clone());
+    if *template != settings.capture_path_template {
+        *template = settings.capture_path_template.clone();
+        rd.set_capture_file_path_template(template);
+    }
+
+    let capture_options = capture_options.get_or_insert_with(|| settings.capture_options.clone());
+    if *capture_options != settings.capture_options {
+        options::apply_changed(&mut *rd, &settings.capture_options, capture_options);
+        *capture_options = settings.capture_options.clone();
+    }
+
+    let capture_keys: Vec<InputButton> = settings
+        .capture_keys
+        .iter()
+        .filter_map(|binding| settings.native_capture_key(binding))
+        .collect();
+    rd.set_capture_keys(&capture_keys);
+
+    let mut focus_toggle_keys = Vec::with_capacity(settings.focus_toggle_keys.len());
+    for binding in &settings.focus_toggle_keys {
+        match binding.input_button() {
+            Some(button) => focus_toggle_keys.push(button),
+            None => warn!("RenderDoc cannot use {:?} as a focus toggle key, ignoring it", binding),
+        }
+    }
+    rd.set_focus_toggle_keys(&focus_toggle_keys);
+}
+
+#[cfg(feature = "capture")]
+fn handle_hotkeys(
+    keys: Option<Res<Input<KeyCode>>>,
+    scan_codes: Option<Res<Input<ScanCode>>>,
+    settings: Res<RenderDocSettings>,
+    windows: Option<Res<Windows>>,
+    mut overlay: ResMut<RenderDocOverlay>,
+    mut tracker: ResMut<capture::CaptureTracker>,
+    mut requests: EventWriter<CaptureRequest>,
+) {
+    let (keys, scan_codes) = match (keys, scan_codes) {
+        (Some(keys), Some(scan_codes)) => (keys, scan_codes),
+        _ => return,
+    };
+
+    let toggle_overlay = settings
+        .overlay_toggle_keys
+        .iter()
+        .any(|binding| binding.just_pressed(&keys, &scan_codes));
+    if toggle_overlay {
+        overlay.enabled = !overlay.enabled;
+    }
+
+    let binding = settings
+        .capture_keys
+        .iter()
+        .find(|binding| binding.just_pressed(&keys, &scan_codes));
+
+    if let Some(binding) = binding {
+        // RenderDoc captures on its own keys, the rest have to be requested from here.
+        if settings.native_capture_key(binding).is_some() {
+            tracker.expect_native_capture();
+        } else {
+            requests.send(CaptureRequest {
+                frames: Some(settings.capture_frames),
+                mode: settings.capture_mode,
+                window: windows
+                    .as_ref()
+                    .and_then(|windows| windows.iter().find(|window| window.is_focused()))
+                    .map(|window| window.id()),
+                open_ui: true,
+                ..default()
+            });
         }
     }
 }
@@ -0,0 +1,250 @@
+use std::fmt;
+#[cfg(feature = "capture")]
+use std::path::Path;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+#[cfg(feature = "capture")]
+use crate::RenderDocApi;
+
+/// Path to RenderDoc's library, or the directory containing it, loaded before
+/// searching the default locations.
+const LIB_PATH_VAR: &str = "RENDERDOC_LIB_PATH";
+
+/// The file name renderdoc-rs loads the library by.
+#[cfg(all(feature = "capture", windows))]
+pub(crate) const LIBRARY_NAME: &str = "renderdoc.dll";
+/// The file name renderdoc-rs loads the library by.
+#[cfg(all(feature = "capture", not(windows)))]
+pub(crate) const LIBRARY_NAME: &str = "librenderdoc.so";
+
+/// Why RenderDoc could not be loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LoadErrorKind {
+    /// The library at `RENDERDOC_LIB_PATH` could not be loaded.
+    InvalidLibPath,
+    /// The library was not found on the library search path.
+    LibraryNotFound,
+    /// A library was loaded, but it does not export `RENDERDOC_GetAPI`.
+    MissingEntryPoint,
+    /// The library does not provide an API version this plugin supports.
+    IncompatibleApi,
+}
+
+/// A directory checked for the RenderDoc library.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchedPath {
+    /// The directory.
+    pub dir: PathBuf,
+    /// Where the directory came from, such as `LD_LIBRARY_PATH`.
+    pub source: &'static str,
+    /// Whether the directory contains the library.
+    pub found: bool,
+}
+
+/// Describes why RenderDoc failed to load, and how to fix it.
+///
+/// Inserted as a resource by [`RenderDocPlugin`](crate::RenderDocPlugin) when
+/// loading fails. Its [`Display`](fmt::Display) output is logged and stored in
+/// [`RenderDocStatus::load_error`](crate::RenderDocStatus::load_error).
+///
+/// # Examples
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// fn report(error: Option<Res<RenderDocLoadError>>) {
+///     if let Some(error) = error {
+///         let found: Vec<_> = error.searched.iter().filter(|path| path.found).collect();
+///         println!("{}: RenderDoc found in {:?}", error.hint, found);
+///     }
+/// }
+/// # bevy::ecs::system::assert_is_system(report);
+/// ```
+#[derive(Resource, Clone, Debug)]
+pub struct RenderDocLoadError {
+    /// What went wrong.
+    pub kind: LoadErrorKind,
+    /// The error reported by the loader.
+    pub message: String,
+    /// The library file names that were looked for.
+    pub library_names: Vec<String>,
+    /// The directories the library was looked for in.
+    pub searched: Vec<SearchedPath>,
+    /// The value of `RENDERDOC_LIB_PATH`, if set.
+    pub lib_path: Option<PathBuf>,
+    /// Whether the library was already loaded into the process, for example
+    /// because the application was launched from RenderDoc.
+    pub already_injected: bool,
+    /// A suggestion for fixing the problem.
+    pub hint: String,
+}
+
+impl fmt::Display for RenderDocLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.message)?;
+        writeln!(f, "  looked for: {}", self.library_names.join(", "))?;
+        if let Some(lib_path) = &self.lib_path {
+            writeln!(f, "  {}: {}", LIB_PATH_VAR, lib_path.display())?;
+        }
+        writeln!(f, "  already injected: {}", self.already_injected)?;
+        for path in &self.searched {
+            let found = if path.found { "found" } else { "not found" };
+            writeln!(f, "  {} ({}): {}", path.dir.display(), path.source, found)?;
+        }
+        write!(f, "  hint: {}", self.hint)
+    }
+}
+
+impl std::error::Error for RenderDocLoadError {}
+
+/// Loads RenderDoc, preloading the library at `RENDERDOC_LIB_PATH` if set.
+#[cfg(feature = "capture")]
+pub(crate) fn load() -> Result<RenderDocApi, Box<RenderDocLoadError>> {
+    let lib_path = std::env::var_os(LIB_PATH_VAR).map(PathBuf::from);
+
+    if let Some(lib_path) = &lib_path {
+        if let Err(e) = preload(lib_path) {
+            return Err(Box::new(diagnose(LoadErrorKind::InvalidLibPath, e.to_string(), lib_path.clone().into())));
+        }
+    }
+
+    RenderDocApi::new().map_err(|e| {
+        let source = std::error::Error::source(&e);
+        let kind = match source.and_then(|e| e.downcast_ref::<libloading::Error>()) {
+            Some(libloading::Error::DlSym { .. } | libloading::Error::GetProcAddress { .. }) => {
+                LoadErrorKind::MissingEntryPoint
+            }
+            Some(_) => LoadErrorKind::LibraryNotFound,
+            None => LoadErrorKind::IncompatibleApi,
+        };
+        // renderdoc-rs's own message doesn't say why the library failed to load.
+        let message = match source {
+            Some(source) => format!("{}: {}", e, source),
+            None => e.to_string(),
+        };
+        Box::new(diagnose(kind, message, lib_path))
+    })
+}
+
+/// Loads the library at `path` and keeps it loaded, so renderdoc-rs finds it
+/// by its file name.
+#[cfg(feature = "capture")]
+fn preload(path: &Path) -> Result<(), libloading::Error> {
+    let path = if path.is_dir() { path.join(LIBRARY_NAME) } else { path.to_owned() };
+    // SAFETY: RenderDoc's library has no initialization routines beyond
+    // hooking the graphics APIs, which is what loading it is for.
+    let library = unsafe { libloading::Library::new(path)? };
+    std::mem::forget(library);
+    Ok(())
+}
+
+#[cfg(feature = "capture")]
+fn diagnose(kind: LoadErrorKind, message: String, lib_path: Option<PathBuf>) -> RenderDocLoadError {
+    let searched: Vec<_> = search_dirs()
+        .into_iter()
+        .map(|(dir, source)| SearchedPath {
+            found: dir.join(LIBRARY_NAME).is_file(),
+            dir,
+            source,
+        })
+        .collect();
+    let already_injected = is_injected();
+
+    let hint = match kind {
+        LoadErrorKind::InvalidLibPath => format!(
+            "Point {} at RenderDoc's {}, or the directory containing it",
+            LIB_PATH_VAR, LIBRARY_NAME
+        ),
+        LoadErrorKind::MissingEntryPoint => format!(
+            "The loaded {} is not RenderDoc's in-application API library, check for a stale copy next to the executable",
+            LIBRARY_NAME
+        ),
+        LoadErrorKind::IncompatibleApi => "Update RenderDoc, this plugin requires API version 1.1.0 or newer".to_owned(),
+        LoadErrorKind::LibraryNotFound => match searched.iter().find(|path| path.found) {
+            Some(path) => format!(
+                "RenderDoc is installed in {}, which is not on the library search path. Set {}={}",
+                path.dir.display(),
+                LIB_PATH_VAR,
+                path.dir.join(LIBRARY_NAME).display()
+            ),
+            None => format!(
+                "Install RenderDoc from https://renderdoc.org/ and set {} to its {}, or launch the application from RenderDoc",
+                LIB_PATH_VAR, LIBRARY_NAME
+            ),
+        },
+    };
+
+    RenderDocLoadError {
+        kind,
+        message,
+        library_names: vec![LIBRARY_NAME.to_owned()],
+        searched,
+        lib_path,
+        already_injected,
+        hint,
+    }
+}
+
+/// Returns the directories the library is commonly found in, and where each
+/// came from.
+#[cfg(feature = "capture")]
+fn search_dirs() -> Vec<(PathBuf, &'static str)> {
+    let mut dirs = Vec::new();
+    if cfg!(not(windows)) {
+        dirs.extend(env_dirs("LD_LIBRARY_PATH"));
+    }
+    dirs.extend(env_dirs("PATH"));
+
+    let common: &[&str] = if cfg!(windows) {
+        &[r"C:\Program Files\RenderDoc"]
+    } else {
+        &[
+            "/usr/lib",
+            "/usr/lib64",
+            "/usr/lib/x86_64-linux-gnu",
+            "/usr/local/lib",
+            "/opt/renderdoc/lib",
+            "/var/lib/flatpak/app/org.renderdoc.RenderDoc/current/active/files/lib",
+            "/snap/renderdoc/current/usr/lib",
+        ]
+    };
+    dirs.extend(common.iter().map(|dir| (PathBuf::from(dir), "common location")));
+
+    if let Some(home) = std::env::var_os("HOME").filter(|_| cfg!(not(windows))) {
+        let flatpak = PathBuf::from(home).join(".local/share/flatpak/app/org.renderdoc.RenderDoc/current/active/files/lib");
+        dirs.push((flatpak, "common location"));
+    }
+
+    dirs
+}
+
+/// Returns the directories listed in the search path variable `var`.
+#[cfg(feature = "capture")]
+fn env_dirs(var: &'static str) -> Vec<(PathBuf, &'static str)> {
+    let value = std::env::var_os(var).unwrap_or_default();
+    std::env::split_paths(&value)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| (dir, var))
+        .collect()
+}
+
+/// Returns whether the library is already loaded into this process.
+#[cfg(feature = "capture")]
+fn is_injected() -> bool {
+    #[cfg(windows)]
+    {
+        libloading::os::windows::Library::open_already_loaded(LIBRARY_NAME).is_ok()
+    }
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string("/proc/self/maps")
+            .map(|maps| maps.lines().any(|line| line.ends_with(LIBRARY_NAME)))
+            .unwrap_or(false)
+    }
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        false
+    }
+}
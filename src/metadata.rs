@@ -0,0 +1,345 @@
+#[cfg(feature = "capture")]
+use std::collections::BTreeMap;
+#[cfg(feature = "capture")]
+use std::fs::File;
+#[cfg(feature = "capture")]
+use std::io::BufWriter;
+#[cfg(feature = "capture")]
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "capture")]
+use bevy::ecs::event::ManualEventReader;
+use bevy::ecs::schedule::StateData;
+use bevy::prelude::*;
+#[cfg(feature = "capture")]
+use bevy::render::{renderer::RenderAdapterInfo, settings::WgpuSettings};
+#[cfg(feature = "capture")]
+use serde::Serialize;
+
+#[cfg(feature = "capture")]
+use crate::shaders::{PipelineSidecar, ShaderPipelines};
+#[cfg(feature = "capture")]
+use crate::spike::FrameTimes;
+#[cfg(feature = "capture")]
+use crate::{CaptureBackend, CaptureCompleted, DisplayInfo, RenderDocCapabilities, RenderDocSettings, RenderDocStatus};
+
+/// The version of the sidecar file layout, increased whenever existing fields change.
+pub const SIDECAR_SCHEMA_VERSION: u32 = 1;
+
+/// How much frame time history is stored in a sidecar.
+#[cfg(feature = "capture")]
+const FRAME_TIME_HISTORY: Duration = Duration::from_secs(5);
+
+/// Extension trait for recording application state in capture metadata.
+pub trait RenderDocAppExt {
+    /// Records the current value of [`State<S>`] in the sidecar of every capture.
+    ///
+    /// # Examples
+    /// ```rust, no_run
+    /// # use bevy::prelude::*;
+    /// # use bevy_renderdoc::*;
+    /// #
+    /// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    /// enum GameState {
+    ///     Menu,
+    ///     InGame,
+    /// }
+    ///
+    /// App::new()
+    ///     .add_plugin(RenderDocPlugin)
+    ///     .add_plugins(DefaultPlugins)
+    ///     .add_state(GameState::Menu)
+    ///     .record_state_in_captures::<GameState>()
+    ///     .run();
+    /// ```
+    fn record_state_in_captures<S: StateData>(&mut self) -> &mut Self;
+}
+
+impl RenderDocAppExt for App {
+    fn record_state_in_captures<S: StateData>(&mut self) -> &mut Self {
+        self.init_resource::<RecordedStates>();
+        self.world
+            .resource_mut::<RecordedStates>()
+            .0
+            .push(current_state::<S>);
+        self
+    }
+}
+
+/// Reads the name and current value of a state.
+type StateReader = fn(&World) -> Option<(String, String)>;
+
+/// The states registered with [`RenderDocAppExt`].
+#[derive(Resource, Default)]
+struct RecordedStates(Vec<StateReader>);
+
+fn current_state<S: StateData>(world: &World) -> Option<(String, String)> {
+    let state = world.get_resource::<State<S>>()?;
+    Some((std::any::type_name::<S>().to_owned(), format!("{:?}", state.current())))
+}
+
+#[cfg(feature = "capture")]
+#[derive(Serialize)]
+struct Sidecar {
+    schema_version: u32,
+    capture: CaptureInfo,
+    elapsed_seconds: Option<f64>,
+    crate_version: &'static str,
+    app_version: Option<String>,
+    git_hash: Option<String>,
+    windows: Vec<WindowInfo>,
+    display: Option<DisplayInfo>,
+    adapter: Option<AdapterInfo>,
+    wgpu_settings: WgpuSettingsInfo,
+    states: BTreeMap<String, String>,
+    cameras: Vec<CameraInfo>,
+    frame_times_ms: Vec<f64>,
+    /// Filled in by [`RenderDocRenderPlugin`](crate::RenderDocRenderPlugin).
+    pipelines: Vec<PipelineSidecar>,
+}
+
+#[cfg(feature = "capture")]
+#[derive(Serialize)]
+struct CaptureInfo {
+    file: String,
+    frame: u64,
+    timestamp: u64,
+    title: Option<String>,
+    comment: Option<String>,
+}
+
+#[cfg(feature = "capture")]
+#[derive(Serialize)]
+struct WindowInfo {
+    id: String,
+    title: String,
+    width: u32,
+    height: u32,
+    scale_factor: f64,
+}
+
+#[cfg(feature = "capture")]
+#[derive(Serialize)]
+struct AdapterInfo {
+    name: String,
+    vendor: usize,
+    device: usize,
+    device_type: String,
+    driver: String,
+    driver_info: String,
+    backend: String,
+}
+
+#[cfg(feature = "capture")]
+#[derive(Serialize)]
+struct WgpuSettingsInfo {
+    backends: Option<String>,
+    power_preference: String,
+    features: String,
+    disabled_features: Option<String>,
+}
+
+#[cfg(feature = "capture")]
+#[derive(Serialize)]
+struct CameraInfo {
+    name: Option<String>,
+    is_active: bool,
+    priority: isize,
+    translation: [f32; 3],
+    rotation: [f32; 4],
+}
+
+#[cfg(feature = "capture")]
+impl Sidecar {
+    fn collect(world: &mut World, capture: &CaptureCompleted) -> Self {
+        let settings = world.resource::<RenderDocSettings>();
+        let (app_version, git_hash) = (settings.app_version.clone(), settings.git_hash.clone());
+
+        let windows = world
+            .get_resource::<Windows>()
+            .map(|windows| {
+                windows
+                    .iter()
+                    .map(|window| WindowInfo {
+                        id: format!("{:?}", window.id()),
+                        title: window.title().to_owned(),
+                        width: window.physical_width(),
+                        height: window.physical_height(),
+                        scale_factor: window.scale_factor(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let display = world.resource::<RenderDocStatus>().display.clone();
+
+        let adapter = world.get_resource::<RenderAdapterInfo>().map(|info| AdapterInfo {
+            name: info.name.clone(),
+            vendor: info.vendor,
+            device: info.device,
+            device_type: format!("{:?}", info.device_type),
+            driver: info.driver.clone(),
+            driver_info: info.driver_info.clone(),
+            backend: format!("{:?}", info.backend),
+        });
+
+        // The render plugin falls back to the defaults when no settings were inserted.
+        let wgpu_settings = world.get_resource::<WgpuSettings>().cloned().unwrap_or_default();
+        let wgpu_settings = WgpuSettingsInfo {
+            backends: wgpu_settings.backends.map(|b| format!("{:?}", b)),
+            power_preference: format!("{:?}", wgpu_settings.power_preference),
+            features: format!("{:?}", wgpu_settings.features),
+            disabled_features: wgpu_settings.disabled_features.map(|f| format!("{:?}", f)),
+        };
+
+        let states = world
+            .get_resource::<RecordedStates>()
+            .map(|states| states.0.iter().filter_map(|state| state(world)).collect())
+            .unwrap_or_default();
+
+        let cameras = world
+            .query::<(&Camera, &GlobalTransform, Option<&Name>)>()
+            .iter(world)
+            .map(|(camera, transform, name)| {
+                let (_, rotation, translation) = transform.to_scale_rotation_translation();
+                CameraInfo {
+                    name: name.map(|name| name.to_string()),
+                    is_active: camera.is_active,
+                    priority: camera.priority,
+                    translation: translation.to_array(),
+                    rotation: rotation.to_array(),
+                }
+            })
+            .collect();
+
+        let mut frame_times_ms: Vec<f64> = world
+            .resource::<FrameTimes>()
+            .recent(FRAME_TIME_HISTORY)
+            .map(|frame_time| frame_time.as_secs_f64() * 1000.0)
+            .collect();
+        frame_times_ms.reverse();
+
+        let pipelines = world
+            .get_resource::<ShaderPipelines>()
+            .map(|pipelines| pipelines.sidecar(world))
+            .unwrap_or_default();
+
+        let timestamp = capture
+            .timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Sidecar {
+            schema_version: SIDECAR_SCHEMA_VERSION,
+            capture: CaptureInfo {
+                file: capture.path.display().to_string(),
+                frame: capture.frame,
+                timestamp: timestamp.as_secs(),
+                title: capture.title.clone(),
+                comment: capture.comment.clone(),
+            },
+            elapsed_seconds: world.get_resource::<Time>().map(|time| time.elapsed_seconds_f64()),
+            crate_version: env!("CARGO_PKG_VERSION"),
+            app_version,
+            git_hash,
+            windows,
+            display,
+            adapter,
+            wgpu_settings,
+            states,
+            cameras,
+            frame_times_ms,
+            pipelines,
+        }
+    }
+
+    /// A one-line description of the capture, for the capture's comments.
+    fn summary(&self) -> String {
+        let mut parts = vec![format!("Frame {}", self.capture.frame)];
+        if let Some(elapsed) = self.elapsed_seconds {
+            parts.push(format!("{:.2} s", elapsed));
+        }
+        for window in &self.windows {
+            parts.push(format!("{} {}x{}", window.title, window.width, window.height));
+        }
+        if let Some(adapter) = &self.adapter {
+            parts.push(format!("{} ({}, {})", adapter.name, adapter.backend, adapter.driver_info));
+        }
+        for (state, value) in &self.states {
+            parts.push(format!("{}: {}", state, value));
+        }
+        parts.push(format!("bevy_renderdoc {}", self.crate_version));
+        if let Some(version) = &self.app_version {
+            parts.push(format!("app {}", version));
+        }
+        if let Some(hash) = &self.git_hash {
+            parts.push(format!("git {}", hash));
+        }
+        parts.join(" | ")
+    }
+}
+
+/// Stores the request's comment, plus a metadata summary, in the comments of
+/// every new capture, and writes a `.json` sidecar next to it.
+///
+/// The request's title goes first in the comments if the loaded library
+/// can't store capture titles.
+#[cfg(feature = "capture")]
+pub(crate) fn annotate_captures<B: CaptureBackend>(
+    world: &mut World,
+    mut reader: Local<ManualEventReader<CaptureCompleted>>,
+) {
+    let captures: Vec<CaptureCompleted> = reader
+        .iter(world.resource::<Events<CaptureCompleted>>())
+        .cloned()
+        .collect();
+    if captures.is_empty() {
+        return;
+    }
+
+    let write_sidecars = world.resource::<RenderDocSettings>().write_sidecars;
+    let capture_title = world.resource::<RenderDocCapabilities>().capture_title;
+    let mut comments = Vec::new();
+    for capture in &captures {
+        let title = capture.title.as_ref().filter(|_| !capture_title);
+        let mut parts: Vec<String> = [title, capture.comment.as_ref()]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+
+        if write_sidecars {
+            let sidecar = Sidecar::collect(world, capture);
+            parts.push(sidecar.summary());
+            write_sidecar(capture, &sidecar);
+        }
+
+        if !parts.is_empty() {
+            comments.push((&capture.path, parts.join("\n\n")));
+        }
+    }
+
+    let mut rd = world.non_send_resource_mut::<B>();
+    for (path, comments) in comments {
+        if !rd.set_capture_file_comments(Some(path), &comments) {
+            debug!("The loaded RenderDoc library does not support capture comments");
+            break;
+        }
+    }
+}
+
+#[cfg(feature = "capture")]
+fn write_sidecar(capture: &CaptureCompleted, sidecar: &Sidecar) {
+    // Nothing to describe if RenderDoc didn't write the capture.
+    if !capture.path.exists() {
+        return;
+    }
+
+    let path = capture.path.with_extension("json");
+    let result = File::create(&path)
+        .map_err(serde_json::Error::io)
+        .and_then(|file| serde_json::to_writer_pretty(BufWriter::new(file), sidecar));
+    if let Err(e) = result {
+        warn!("Failed to write RenderDoc capture metadata to {}: {}", path.display(), e);
+    }
+}
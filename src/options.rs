@@ -0,0 +1,257 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "capture")]
+use bevy::prelude::*;
+#[cfg(feature = "capture")]
+use renderdoc::CaptureOption;
+
+#[cfg(feature = "capture")]
+use crate::CaptureBackend;
+
+/// RenderDoc capture options, set through [`RenderDocSettings::capture_options`](crate::RenderDocSettings::capture_options).
+///
+/// Options left as [`None`] keep RenderDoc's defaults. The plugin applies the
+/// options when it is built, before the render device is created, and again
+/// whenever the settings change. Changing `api_validation`, `capture_all_cmd_lists`,
+/// `delay_for_debugger` or `hook_into_children` at runtime only takes effect
+/// after a restart.
+///
+/// # Examples
+/// ```rust, no_run
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// App::new()
+///     .insert_resource(RenderDocSettings {
+///         capture_options: CaptureOptions::validation().capture_callstacks(true),
+///         ..default()
+///     })
+///     .add_plugin(RenderDocPlugin)
+///     .add_plugins(DefaultPlugins)
+///     .run();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CaptureOptions {
+    /// Lets the application enable vertical synchronization.
+    pub allow_vsync: Option<bool>,
+    /// Lets the application enter fullscreen mode.
+    pub allow_fullscreen: Option<bool>,
+    /// Enables the graphics API's validation layers and records their messages.
+    pub api_validation: Option<bool>,
+    /// Captures CPU callstacks for API events.
+    pub capture_callstacks: Option<bool>,
+    /// Only captures callstacks for draws, requires `capture_callstacks`.
+    pub callstacks_only_draws: Option<bool>,
+    /// Seconds to wait for a debugger to attach after RenderDoc is injected.
+    pub delay_for_debugger: Option<u32>,
+    /// Checks writes to mapped buffers for out-of-bounds modifications.
+    pub verify_buffer_access: Option<bool>,
+    /// Injects RenderDoc into child processes.
+    pub hook_into_children: Option<bool>,
+    /// Includes every resource in captures, not only the ones the frame uses.
+    pub ref_all_resources: Option<bool>,
+    /// Saves the initial contents of every resource, even seemingly overwritten ones.
+    pub save_all_initials: Option<bool>,
+    /// Captures all command lists from application start.
+    pub capture_all_cmd_lists: Option<bool>,
+    /// Mutes the API's debug output when `api_validation` is enabled.
+    pub debug_output_mute: Option<bool>,
+}
+
+impl CaptureOptions {
+    /// Options leaving every RenderDoc default untouched.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps every resource and its initial contents in captures, so any
+    /// shader can be debugged with the data it read.
+    pub fn shader_debugging() -> Self {
+        Self::new()
+            .ref_all_resources(true)
+            .save_all_initials(true)
+    }
+
+    /// Enables API validation and buffer access checks, keeping debug output.
+    pub fn validation() -> Self {
+        Self::new()
+            .api_validation(true)
+            .debug_output_mute(false)
+            .verify_buffer_access(true)
+    }
+
+    /// Disables everything that slows down the application or grows captures.
+    pub fn minimal_overhead() -> Self {
+        Self::new()
+            .api_validation(false)
+            .capture_callstacks(false)
+            .verify_buffer_access(false)
+            .ref_all_resources(false)
+            .save_all_initials(false)
+            .capture_all_cmd_lists(false)
+    }
+
+    /// Sets [`allow_vsync`](Self::allow_vsync).
+    pub fn allow_vsync(mut self, enabled: bool) -> Self {
+        self.allow_vsync = Some(enabled);
+        self
+    }
+
+    /// Sets [`allow_fullscreen`](Self::allow_fullscreen).
+    pub fn allow_fullscreen(mut self, enabled: bool) -> Self {
+        self.allow_fullscreen = Some(enabled);
+        self
+    }
+
+    /// Sets [`api_validation`](Self::api_validation).
+    pub fn api_validation(mut self, enabled: bool) -> Self {
+        self.api_validation = Some(enabled);
+        self
+    }
+
+    /// Sets [`capture_callstacks`](Self::capture_callstacks).
+    pub fn capture_callstacks(mut self, enabled: bool) -> Self {
+        self.capture_callstacks = Some(enabled);
+        self
+    }
+
+    /// Sets [`callstacks_only_draws`](Self::callstacks_only_draws).
+    pub fn callstacks_only_draws(mut self, enabled: bool) -> Self {
+        self.callstacks_only_draws = Some(enabled);
+        self
+    }
+
+    /// Sets [`delay_for_debugger`](Self::delay_for_debugger).
+    pub fn delay_for_debugger(mut self, seconds: u32) -> Self {
+        self.delay_for_debugger = Some(seconds);
+        self
+    }
+
+    /// Sets [`verify_buffer_access`](Self::verify_buffer_access).
+    pub fn verify_buffer_access(mut self, enabled: bool) -> Self {
+        self.verify_buffer_access = Some(enabled);
+        self
+    }
+
+    /// Sets [`hook_into_children`](Self::hook_into_children).
+    pub fn hook_into_children(mut self, enabled: bool) -> Self {
+        self.hook_into_children = Some(enabled);
+        self
+    }
+
+    /// Sets [`ref_all_resources`](Self::ref_all_resources).
+    pub fn ref_all_resources(mut self, enabled: bool) -> Self {
+        self.ref_all_resources = Some(enabled);
+        self
+    }
+
+    /// Sets [`save_all_initials`](Self::save_all_initials).
+    pub fn save_all_initials(mut self, enabled: bool) -> Self {
+        self.save_all_initials = Some(enabled);
+        self
+    }
+
+    /// Sets [`capture_all_cmd_lists`](Self::capture_all_cmd_lists).
+    pub fn capture_all_cmd_lists(mut self, enabled: bool) -> Self {
+        self.capture_all_cmd_lists = Some(enabled);
+        self
+    }
+
+    /// Sets [`debug_output_mute`](Self::debug_output_mute).
+    pub fn debug_output_mute(mut self, enabled: bool) -> Self {
+        self.debug_output_mute = Some(enabled);
+        self
+    }
+
+    /// Returns the options that are set, with the values RenderDoc expects.
+    #[cfg(feature = "capture")]
+    pub(crate) fn entries(&self) -> Vec<(CaptureOption, u32)> {
+        let flags = [
+            (CaptureOption::AllowVSync, self.allow_vsync),
+            (CaptureOption::AllowFullscreen, self.allow_fullscreen),
+            (CaptureOption::ApiValidation, self.api_validation),
+            (CaptureOption::CaptureCallstacks, self.capture_callstacks),
+            (CaptureOption::CaptureCallstacksOnlyDraws, self.callstacks_only_draws),
+            (CaptureOption::VerifyMapWrites, self.verify_buffer_access),
+            (CaptureOption::HookIntoChildren, self.hook_into_children),
+            (CaptureOption::RefAllResources, self.ref_all_resources),
+            (CaptureOption::SaveAllInitials, self.save_all_initials),
+            (CaptureOption::CaptureAllCmdLists, self.capture_all_cmd_lists),
+            (CaptureOption::DebugOutputMute, self.debug_output_mute),
+        ];
+
+        let mut entries: Vec<_> = flags
+            .into_iter()
+            .filter_map(|(option, enabled)| enabled.map(|enabled| (option, enabled as u32)))
+            .collect();
+        if let Some(seconds) = self.delay_for_debugger {
+            entries.push((CaptureOption::DelayForDebugger, seconds));
+        }
+        entries
+    }
+
+    /// Returns whether `option` only takes effect when the render device or
+    /// process is created, so changing it at runtime has no effect.
+    #[cfg(feature = "capture")]
+    pub(crate) fn requires_restart(option: CaptureOption) -> bool {
+        matches!(
+            option,
+            CaptureOption::ApiValidation
+                | CaptureOption::CaptureAllCmdLists
+                | CaptureOption::DelayForDebugger
+                | CaptureOption::HookIntoChildren
+        )
+    }
+}
+
+/// Sets every option in `options` that differs from `previous`, logging
+/// options RenderDoc rejects or that can no longer take effect.
+#[cfg(feature = "capture")]
+pub(crate) fn apply_changed<B: CaptureBackend>(
+    rd: &mut B,
+    options: &CaptureOptions,
+    previous: &CaptureOptions,
+) {
+    let previous = previous.entries();
+    for (option, value) in options.entries() {
+        if previous.contains(&(option, value)) {
+            continue;
+        }
+        if !rd.set_capture_option(option, value) {
+            warn!("RenderDoc rejected capture option {:?} = {}", option, value);
+        } else if CaptureOptions::requires_restart(option) {
+            warn!("RenderDoc capture option {:?} only takes effect after a restart", option);
+        }
+    }
+}
+
+impl FromStr for CaptureOptions {
+    type Err = UnknownPreset;
+
+    /// Parses a preset name: `shader-debugging`, `validation` or `minimal-overhead`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "shader-debugging" => Ok(Self::shader_debugging()),
+            "validation" => Ok(Self::validation()),
+            "minimal-overhead" => Ok(Self::minimal_overhead()),
+            _ => Err(UnknownPreset(name.to_owned())),
+        }
+    }
+}
+
+/// Error returned when parsing an unknown [`CaptureOptions`] preset name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownPreset(pub String);
+
+impl fmt::Display for UnknownPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown capture options preset \"{}\", expected shader-debugging, validation or minimal-overhead",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownPreset {}
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+#[cfg(feature = "capture")]
+use renderdoc::OverlayBits;
+
+#[cfg(feature = "capture")]
+use crate::CaptureBackend;
+
+/// Which parts of RenderDoc's in-application overlay are shown.
+///
+/// Changes are applied to RenderDoc on the next frame. The overlay is hidden
+/// by default and can be toggled with [`RenderDocSettings::overlay_toggle_keys`](crate::RenderDocSettings::overlay_toggle_keys).
+///
+/// # Examples
+#[cfg_attr(feature = "capture", doc = "```rust")]
+#[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::renderdoc::OverlayBits;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let mut app = App::new();
+/// app.add_plugins(MinimalPlugins)
+///     .add_plugin(MockRenderDocPlugin::default());
+///
+/// app.update();
+/// let mock = app.world.non_send_resource::<MockBackend>();
+/// assert!(!mock.get_overlay_bits().contains(OverlayBits::ENABLED));
+///
+/// app.world.resource_mut::<RenderDocOverlay>().enabled = true;
+/// app.update();
+/// let mock = app.world.non_send_resource::<MockBackend>();
+/// assert_eq!(mock.get_overlay_bits(), OverlayBits::DEFAULT);
+/// ```
+#[derive(Resource, Reflect, Clone, Debug, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct RenderDocOverlay {
+    /// Shows the overlay. Defaults to `false`.
+    pub enabled: bool,
+    /// Shows the average, minimum and maximum frame time. Defaults to `true`.
+    pub frame_rate: bool,
+    /// Shows the current frame number. Defaults to `true`.
+    pub frame_number: bool,
+    /// Shows the captures taken so far. Defaults to `true`.
+    pub capture_list: bool,
+}
+
+#[cfg(feature = "capture")]
+impl RenderDocOverlay {
+    /// Returns the overlay bits RenderDoc uses for this configuration.
+    pub(crate) fn bits(&self) -> OverlayBits {
+        let mut bits = OverlayBits::NONE;
+        bits.set(OverlayBits::ENABLED, self.enabled);
+        bits.set(OverlayBits::FRAME_RATE, self.frame_rate);
+        bits.set(OverlayBits::FRAME_NUMBER, self.frame_number);
+        bits.set(OverlayBits::CAPTURE_LIST, self.capture_list);
+        bits
+    }
+}
+
+impl Default for RenderDocOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            frame_rate: true,
+            frame_number: true,
+            capture_list: true,
+        }
+    }
+}
+
+#[cfg(feature = "capture")]
+pub(crate) fn apply_overlay<B: CaptureBackend>(overlay: Res<RenderDocOverlay>, mut rd: NonSendMut<B>) {
+    if overlay.is_changed() {
+        rd.mask_overlay_bits(OverlayBits::NONE, overlay.bits());
+    }
+}
@@ -0,0 +1,284 @@
+#[cfg(feature = "capture")]
+use std::backtrace::Backtrace;
+#[cfg(feature = "capture")]
+use std::fs::{self, File};
+#[cfg(feature = "capture")]
+use std::io::BufWriter;
+#[cfg(feature = "capture")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "capture")]
+use std::ptr;
+#[cfg(feature = "capture")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "capture")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "capture")]
+use std::time::SystemTime;
+
+#[cfg(feature = "capture")]
+use bevy::prelude::*;
+#[cfg(feature = "capture")]
+use serde::Serialize;
+
+#[cfg(feature = "capture")]
+use crate::capture::{absolute, wildcard_device, CaptureTracker};
+#[cfg(feature = "capture")]
+use crate::{CaptureBackend, CaptureRequest, RenderDocApi, SIDECAR_SCHEMA_VERSION};
+
+/// Marks that the next launch should capture its first frame.
+#[cfg(feature = "capture")]
+const MARKER_FILE: &str = "capture_next_launch";
+/// Describes the most recent panic.
+#[cfg(feature = "capture")]
+const REPORT_FILE: &str = "last_panic.json";
+
+/// Configuration for the panic hook, which saves the frame capture in
+/// progress when the application panics.
+///
+/// Enable it by setting [`RenderDocSettings::panic_capture`](crate::RenderDocSettings::panic_capture).
+/// On panic, the hook ends any frame capture in progress, such as a
+/// [`CaptureMode::Span`](crate::CaptureMode::Span) capture or a
+/// [`CaptureScope`](crate::CaptureScope), stores the panic message and
+/// backtrace in its comments, and writes a `last_panic.json` report next to the
+/// captures. Panics are then handled by the previously installed hook.
+///
+/// # Examples
+/// ```rust, no_run
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// App::new()
+///     .insert_resource(RenderDocSettings {
+///         panic_capture: Some(PanicCapture {
+///             capture_next_launch: true,
+///             ..default()
+///         }),
+///         ..default()
+///     })
+///     .add_plugins(DefaultPlugins.build().with_renderdoc())
+///     .run();
+/// ```
+#[derive(Clone, Debug)]
+pub struct PanicCapture {
+    /// Stores the backtrace of the panic in the capture comments and the
+    /// report. Defaults to `true`.
+    pub backtrace: bool,
+    /// Writes a `capture_next_launch` marker file next to the captures, so the
+    /// next launch captures its first frame and deletes the marker. Defaults to `false`.
+    ///
+    /// # Examples
+    #[cfg_attr(feature = "capture", doc = "```rust")]
+    #[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+    /// # use bevy::prelude::*;
+    /// # use bevy_renderdoc::testing::*;
+    /// # use bevy_renderdoc::*;
+    /// #
+    /// let dir = std::env::temp_dir().join(format!("bevy_renderdoc_marker_{}", std::process::id()));
+    /// let config = PanicCapture {
+    ///     capture_next_launch: true,
+    ///     ..default()
+    /// };
+    ///
+    /// // The previous run panicked.
+    /// let mut mock = MockBackend::default();
+    /// mock.set_capture_file_path_template(&dir.join("capture"));
+    /// simulate_panic(&mut mock, &config, 120, "out of bounds");
+    /// assert!(dir.join("capture_next_launch").exists());
+    ///
+    /// let mut app = App::new();
+    /// app.insert_resource(RenderDocSettings {
+    ///     capture_path_template: dir.join("capture"),
+    ///     panic_capture: Some(config),
+    ///     ..default()
+    /// })
+    /// .add_plugins(MinimalPlugins)
+    /// .add_plugin(MockRenderDocPlugin::default());
+    /// assert!(!dir.join("capture_next_launch").exists());
+    ///
+    /// app.update();
+    /// app.update();
+    ///
+    /// let mock = app.world.non_send_resource::<MockBackend>();
+    /// assert_eq!(mock.triggered_frames(), vec![0]);
+    /// let completed = app.world.resource::<Events<CaptureCompleted>>();
+    /// let mut reader = completed.get_reader();
+    /// let capture = reader.iter(completed).next().unwrap();
+    /// assert_eq!(capture.title.as_deref(), Some("First frame after a panic"));
+    /// assert_eq!(capture.comment.as_deref(), Some("Panicked: out of bounds"));
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub capture_next_launch: bool,
+}
+
+impl Default for PanicCapture {
+    fn default() -> Self {
+        Self {
+            backtrace: true,
+            capture_next_launch: false,
+        }
+    }
+}
+
+/// What is known about a panic, written to [`REPORT_FILE`].
+#[cfg(feature = "capture")]
+#[derive(Serialize)]
+struct PanicReport {
+    schema_version: u32,
+    message: String,
+    location: Option<String>,
+    thread: Option<String>,
+    frame: u64,
+    timestamp: u64,
+    /// The capture that was in progress, if any.
+    capture: Option<String>,
+    backtrace: Option<String>,
+    capture_next_launch: bool,
+}
+
+/// The current frame, shared with the panic hook.
+#[cfg(feature = "capture")]
+#[derive(Resource, Clone, Default)]
+pub(crate) struct PanicFrame(pub(crate) Arc<AtomicU64>);
+
+/// Installs a panic hook that saves the frame capture in progress, using its
+/// own handle to RenderDoc.
+#[cfg(feature = "capture")]
+pub(crate) fn install(app: &mut App, rd: RenderDocApi, config: PanicCapture) {
+    let frame = PanicFrame::default();
+    app.insert_resource(frame.clone())
+        .add_system_to_stage(CoreStage::First, record_frame);
+
+    let rd = Mutex::new(rd);
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // Only the first of several threads panicking at once saves the capture.
+        if let Ok(mut rd) = rd.try_lock() {
+            let message = match info.payload().downcast_ref::<&str>() {
+                Some(message) => message.to_string(),
+                None => match info.payload().downcast_ref::<String>() {
+                    Some(message) => message.clone(),
+                    None => "Box<dyn Any>".to_owned(),
+                },
+            };
+            let location = info.location().map(|location| location.to_string());
+            save_capture(&mut *rd, &config, &frame, message, location);
+        }
+        previous(info);
+    }));
+}
+
+#[cfg(feature = "capture")]
+fn record_frame(tracker: Res<CaptureTracker>, frame: Res<PanicFrame>) {
+    frame.0.store(tracker.frame(), Ordering::Relaxed);
+}
+
+/// Ends the capture in progress, annotates it with the panic, and writes the
+/// report and marker files.
+#[cfg(feature = "capture")]
+pub(crate) fn save_capture(
+    rd: &mut impl CaptureBackend,
+    config: &PanicCapture,
+    frame: &PanicFrame,
+    message: String,
+    location: Option<String>,
+) {
+    let backtrace = config
+        .backtrace
+        .then(|| Backtrace::force_capture().to_string());
+    let summary = match &location {
+        Some(location) => format!("Panicked at {}: {}", location, message),
+        None => format!("Panicked: {}", message),
+    };
+
+    let mut capture = None;
+    if rd.is_frame_capturing() {
+        rd.end_frame_capture(wildcard_device(), ptr::null());
+        capture = rd
+            .get_num_captures()
+            .checked_sub(1)
+            .and_then(|index| rd.get_capture(index))
+            .map(|(path, _)| absolute(&path));
+
+        let comments = match &backtrace {
+            Some(backtrace) => format!("{}\n\n{}", summary, backtrace),
+            None => summary.clone(),
+        };
+        rd.set_capture_file_comments(capture.as_deref(), &comments);
+    }
+
+    let dir = capture_dir(&rd.get_capture_file_path_template());
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("Failed to create RenderDoc capture directory {}: {}", dir.display(), e);
+        return;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let report = PanicReport {
+        schema_version: SIDECAR_SCHEMA_VERSION,
+        message,
+        location,
+        thread: std::thread::current().name().map(str::to_owned),
+        frame: frame.0.load(Ordering::Relaxed),
+        timestamp: timestamp.as_secs(),
+        capture: capture.as_ref().map(|path| path.display().to_string()),
+        backtrace,
+        capture_next_launch: config.capture_next_launch,
+    };
+
+    // Logging may be what panicked, so report straight to stderr.
+    let report_path = dir.join(REPORT_FILE);
+    let result = File::create(&report_path)
+        .map_err(serde_json::Error::io)
+        .and_then(|file| serde_json::to_writer_pretty(BufWriter::new(file), &report));
+    match result {
+        Ok(()) => eprintln!("RenderDoc panic report written to {}", report_path.display()),
+        Err(e) => eprintln!("Failed to write RenderDoc panic report to {}: {}", report_path.display(), e),
+    }
+    if let Some(capture) = &capture {
+        eprintln!("RenderDoc capture in progress saved to {}", capture.display());
+    }
+
+    if config.capture_next_launch {
+        let marker = dir.join(MARKER_FILE);
+        if let Err(e) = fs::write(&marker, &summary) {
+            eprintln!("Failed to write RenderDoc marker file {}: {}", marker.display(), e);
+        }
+    }
+}
+
+/// Returns the directory capture files named after `template` are written to.
+#[cfg(feature = "capture")]
+fn capture_dir(template: &Path) -> PathBuf {
+    template
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(Path::to_owned)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Captures the first frame if the previous run panicked and left a marker
+/// file next to the captures, deleting the marker.
+#[cfg(feature = "capture")]
+pub(crate) fn honor_marker(app: &mut App, template: &Path) {
+    let marker = capture_dir(template).join(MARKER_FILE);
+    let reason = match fs::read_to_string(&marker) {
+        Ok(reason) => reason,
+        Err(_) => return,
+    };
+    // A marker that can't be removed would capture every launch from now on.
+    if let Err(e) = fs::remove_file(&marker) {
+        app.add_startup_system(move || warn!("Failed to remove RenderDoc marker file: {}", e));
+        return;
+    }
+
+    app.add_startup_system(move |mut requests: EventWriter<CaptureRequest>| {
+        info!("The previous run panicked, capturing the first frame");
+        requests.send(CaptureRequest {
+            title: Some("First frame after a panic".into()),
+            comment: Some(reason.clone()),
+            ..default()
+        });
+    });
+}
@@ -0,0 +1,202 @@
+#[cfg(feature = "capture")]
+use std::path::Path;
+
+#[cfg(feature = "capture")]
+use bevy::{app::AppExit, prelude::*};
+#[cfg(feature = "capture")]
+use sysinfo::{Pid, ProcessExt, ProcessRefreshKind, SystemExt};
+
+#[cfg(feature = "capture")]
+use crate::{CaptureBackend, RenderDocSettings};
+
+/// When the plugin launches the RenderDoc replay UI for a capture.
+///
+/// Applies to captures taken by the capture keys and to [`CaptureRequest`]s
+/// with [`open_ui`](crate::CaptureRequest::open_ui) set. A replay UI that is
+/// already connected to the application receives new captures on its own, so
+/// it is never launched a second time.
+///
+/// [`CaptureRequest`]: crate::CaptureRequest
+///
+/// # Examples
+#[cfg_attr(feature = "capture", doc = "```rust")]
+#[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let mut app = App::new();
+/// app.insert_resource(RenderDocSettings {
+///     replay_ui: ReplayUiPolicy::FirstCapture,
+///     ..default()
+/// })
+/// .add_plugins(MinimalPlugins)
+/// .add_plugin(MockRenderDocPlugin::default());
+///
+/// for _ in 0..2 {
+///     app.world.send_event(CaptureRequest {
+///         open_ui: true,
+///         ..default()
+///     });
+///     app.update();
+/// }
+///
+/// // The replay UI is launched once, opening the first capture.
+/// let mock = app.world.non_send_resource::<MockBackend>();
+/// let launches: Vec<_> = mock
+///     .calls
+///     .iter()
+///     .filter_map(|(_, call)| match call {
+///         BackendCall::LaunchReplayUi { extra_opts, .. } => extra_opts.clone(),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(launches.len(), 1);
+/// assert!(launches[0].ends_with("bevy_capture_frame0.rdc\""));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ReplayUiPolicy {
+    /// Never launch the replay UI.
+    Never,
+    /// Launch the replay UI for the first capture of the session only.
+    FirstCapture,
+    /// Launch the replay UI for every capture, unless one is already running.
+    #[default]
+    EveryCapture,
+    /// Never launch the replay UI, only bring a connected one to the foreground.
+    ///
+    /// Requires RenderDoc API 1.5.0. With older libraries, the capture only
+    /// shows up in the connected UI's capture list.
+    ///
+    /// # Examples
+    #[cfg_attr(feature = "capture", doc = "```rust")]
+    #[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+    /// # use bevy::prelude::*;
+    /// # use bevy_renderdoc::testing::*;
+    /// # use bevy_renderdoc::*;
+    /// #
+    /// let mut backend = MockBackend::default();
+    /// backend.target_control_connected = true;
+    ///
+    /// let mut app = App::new();
+    /// app.insert_resource(RenderDocSettings {
+    ///     replay_ui: ReplayUiPolicy::FocusExisting,
+    ///     ..default()
+    /// })
+    /// .add_plugins(MinimalPlugins)
+    /// .add_plugin(MockRenderDocPlugin {
+    ///     backend,
+    ///     ..default()
+    /// });
+    ///
+    /// app.world.send_event(CaptureRequest {
+    ///     open_ui: true,
+    ///     ..default()
+    /// });
+    /// app.update();
+    ///
+    /// let mock = app.world.non_send_resource::<MockBackend>();
+    /// assert_eq!(mock.frames_of(&BackendCall::ShowReplayUi).len(), 1);
+    /// assert!(!mock
+    ///     .calls
+    ///     .iter()
+    ///     .any(|(_, call)| matches!(call, BackendCall::LaunchReplayUi { .. })));
+    /// ```
+    FocusExisting,
+}
+
+/// Tracks the replay UI launched by the plugin.
+#[cfg(feature = "capture")]
+#[derive(Resource, Default)]
+pub(crate) struct ReplayUi {
+    pid: Option<Pid>,
+    launched: bool,
+    system: sysinfo::System,
+}
+
+#[cfg(feature = "capture")]
+impl ReplayUi {
+    /// Opens `capture` in the replay UI, as far as `policy` allows.
+    pub(crate) fn open<B: CaptureBackend>(&mut self, rd: &mut B, capture: &Path, policy: ReplayUiPolicy) {
+        let may_launch = match policy {
+            ReplayUiPolicy::Never => return,
+            ReplayUiPolicy::FirstCapture => !self.launched,
+            ReplayUiPolicy::EveryCapture => true,
+            ReplayUiPolicy::FocusExisting => {
+                if !rd.show_replay_ui() {
+                    debug!("No RenderDoc Replay UI to bring to the foreground");
+                }
+                return;
+            }
+        };
+
+        if !may_launch {
+            return;
+        }
+
+        // The replay UI opens capture files passed on its command line.
+        let extra_opts = format!("\"{}\"", capture.display());
+        self.launch_with(rd, Some(&extra_opts));
+    }
+
+    /// Launches the replay UI, unless one is already running or connected.
+    pub(crate) fn launch<B: CaptureBackend>(&mut self, rd: &mut B) {
+        self.launch_with(rd, None);
+    }
+
+    fn launch_with<B: CaptureBackend>(&mut self, rd: &mut B, extra_opts: Option<&str>) {
+        if rd.is_target_control_connected() {
+            debug!("RenderDoc Replay UI is already connected, not launching another one");
+            return;
+        }
+        // Avoid launching multiple instances of the replay ui
+        if self.is_running() {
+            return;
+        }
+
+        match rd.launch_replay_ui(true, extra_opts) {
+            Some(pid) => {
+                self.pid = Some(Pid::from(pid as i32));
+                self.launched = true;
+                info!("Launching RenderDoc Replay UI");
+            }
+            None => error!("Failed to launch RenderDoc Replay UI"),
+        }
+    }
+
+    fn is_running(&mut self) -> bool {
+        match self.pid {
+            Some(pid) => self
+                .system
+                .refresh_process_specifics(pid, ProcessRefreshKind::new()),
+            None => false,
+        }
+    }
+
+    /// Closes the replay UI launched by the plugin, if it is still running.
+    fn close(&mut self) {
+        let pid = match self.pid.take() {
+            Some(pid) => pid,
+            None => return,
+        };
+        if self.system.refresh_process_specifics(pid, ProcessRefreshKind::new()) {
+            if let Some(process) = self.system.process(pid) {
+                info!("Closing RenderDoc Replay UI");
+                process.kill();
+            }
+        }
+    }
+}
+
+/// Closes the launched replay UI when the application exits, if
+/// [`RenderDocSettings::close_replay_ui_on_exit`] is set.
+#[cfg(feature = "capture")]
+pub(crate) fn close_replay_ui_on_exit(
+    mut exit: EventReader<AppExit>,
+    settings: Res<RenderDocSettings>,
+    mut replay_ui: ResMut<ReplayUi>,
+) {
+    if exit.iter().last().is_some() && settings.close_replay_ui_on_exit {
+        replay_ui.close();
+    }
+}
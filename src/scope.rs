@@ -0,0 +1,185 @@
+#[cfg(feature = "capture")]
+use std::ptr;
+#[cfg(feature = "capture")]
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use bevy::render::render_graph::{Node, NodeRunError, RenderGraphContext};
+#[cfg(feature = "capture")]
+use bevy::render::render_resource::CommandEncoderDescriptor;
+use bevy::render::renderer::RenderContext;
+#[cfg(feature = "capture")]
+use bevy::render::renderer::RenderQueue;
+#[cfg(feature = "capture")]
+use bevy::render::Extract;
+
+#[cfg(feature = "capture")]
+use crate::capture::wildcard_device;
+#[cfg(feature = "capture")]
+use crate::{CaptureBackend, RenderDocApi};
+
+/// Render graph nodes that limit [`CaptureMode::Scope`](crate::CaptureMode::Scope)
+/// captures to the work between them.
+///
+/// Add a [`Begin`](Self::Begin) and an [`End`](Self::End) node to a render
+/// graph, with edges running from `Begin` through the nodes of interest to
+/// `End`. The nodes do nothing until a scope capture is requested. Both nodes
+/// submit the commands encoded so far, so the capture holds exactly the
+/// commands in between.
+///
+/// Nodes in a sub-graph that runs once per view, such as `core_3d`, capture
+/// the first view only, so every frame writes a single capture.
+///
+/// Requires [`RenderDocRenderPlugin`](crate::RenderDocRenderPlugin).
+///
+/// # Examples
+/// ```rust, no_run
+/// # use bevy::prelude::*;
+/// # use bevy::core_pipeline::core_3d;
+/// # use bevy::pbr::draw_3d_graph;
+/// # use bevy::render::{render_graph::RenderGraph, RenderApp};
+/// # use bevy_renderdoc::*;
+/// #
+/// let mut app = App::new();
+/// app.add_plugin(RenderDocPlugin)
+///     .add_plugins(DefaultPlugins)
+///     .add_plugin(RenderDocRenderPlugin);
+///
+/// // Capture only the shadow pass.
+/// let render_app = app.sub_app_mut(RenderApp);
+/// let mut graph = render_app.world.resource_mut::<RenderGraph>();
+/// let core_3d = graph.get_sub_graph_mut(core_3d::graph::NAME).unwrap();
+/// core_3d.add_node("renderdoc_begin", RenderDocCaptureScope::Begin);
+/// core_3d.add_node("renderdoc_end", RenderDocCaptureScope::End);
+/// core_3d.add_node_edge("renderdoc_begin", draw_3d_graph::node::SHADOW_PASS).unwrap();
+/// core_3d.add_node_edge(draw_3d_graph::node::SHADOW_PASS, "renderdoc_end").unwrap();
+///
+/// app.world.send_event(CaptureRequest::scope());
+/// app.run();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RenderDocCaptureScope {
+    /// Starts the capture.
+    Begin,
+    /// Ends the capture and writes it to disk.
+    End,
+}
+
+/// The number of frames scope captures are still requested for, in the main world.
+#[cfg(feature = "capture")]
+#[derive(Resource, Default)]
+pub(crate) struct ArmedScope {
+    pub(crate) frames: u32,
+    /// The title of the requested captures, if any.
+    pub(crate) title: Option<String>,
+}
+
+/// The render world's side of scope captures.
+#[cfg(feature = "capture")]
+#[derive(Resource)]
+pub(crate) struct CaptureScopeState {
+    armed: bool,
+    title: Option<String>,
+    rd: Mutex<ScopeBackend>,
+}
+
+#[cfg(feature = "capture")]
+struct ScopeBackend {
+    rd: RenderDocApi,
+    capturing: bool,
+    /// Whether this frame's capture has ended already.
+    done: bool,
+}
+
+#[cfg(feature = "capture")]
+impl CaptureScopeState {
+    pub(crate) fn new(rd: RenderDocApi) -> Self {
+        Self {
+            armed: false,
+            title: None,
+            rd: Mutex::new(ScopeBackend {
+                rd,
+                capturing: false,
+                done: false,
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "capture")]
+impl Node for RenderDocCaptureScope {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let state = match world.get_resource::<CaptureScopeState>() {
+            Some(state) if state.armed => state,
+            _ => return Ok(()),
+        };
+        let mut backend = match state.rd.lock() {
+            Ok(backend) => backend,
+            Err(_) => return Ok(()),
+        };
+
+        match self {
+            Self::Begin if !backend.capturing && !backend.done => {
+                flush(render_context, world);
+                backend.rd.start_frame_capture(wildcard_device(), ptr::null());
+                if let Some(title) = &state.title {
+                    backend.rd.set_capture_title(title);
+                }
+                backend.capturing = true;
+            }
+            Self::End if backend.capturing => {
+                flush(render_context, world);
+                backend.rd.end_frame_capture(wildcard_device(), ptr::null());
+                backend.capturing = false;
+                backend.done = true;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "capture"))]
+impl Node for RenderDocCaptureScope {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        _render_context: &mut RenderContext,
+        _world: &World,
+    ) -> Result<(), NodeRunError> {
+        Ok(())
+    }
+}
+
+/// Submits the commands encoded so far and starts a new encoder.
+#[cfg(feature = "capture")]
+fn flush(render_context: &mut RenderContext, world: &World) {
+    let encoder = render_context
+        .render_device
+        .create_command_encoder(&CommandEncoderDescriptor::default());
+    let encoder = std::mem::replace(&mut render_context.command_encoder, encoder);
+    world.resource::<RenderQueue>().submit([encoder.finish()]);
+}
+
+/// Counts down the frames scope captures were requested for.
+#[cfg(feature = "capture")]
+pub(crate) fn count_scope_frames(mut armed: ResMut<ArmedScope>) {
+    armed.frames = armed.frames.saturating_sub(1);
+    if armed.frames == 0 {
+        armed.title = None;
+    }
+}
+
+#[cfg(feature = "capture")]
+pub(crate) fn extract_armed_scope(armed: Extract<Res<ArmedScope>>, mut state: ResMut<CaptureScopeState>) {
+    state.armed = armed.frames > 0;
+    state.title = armed.title.clone();
+    if let Ok(backend) = state.rd.get_mut() {
+        backend.done = false;
+    }
+}
@@ -0,0 +1,194 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+#[cfg(feature = "capture")]
+use renderdoc::InputButton;
+
+use crate::{
+    CaptureMode, CaptureOptions, DeviceErrorCapture, KeyBinding, OpenCaptureLimit, PanicCapture, ReplayUiPolicy, SpikeCapture,
+};
+
+/// Configuration for [`RenderDocPlugin`](crate::RenderDocPlugin).
+///
+/// Insert this resource before adding the plugin to override the defaults.
+/// Changes made at runtime are applied to RenderDoc on the next frame.
+///
+/// # Examples
+/// ```rust, no_run
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// App::new()
+///     .insert_resource(RenderDocSettings {
+///         capture_keys: vec![KeyBinding::new(KeyCode::C).with_modifier(KeyCode::LControl)],
+///         ..default()
+///     })
+///     .add_plugin(RenderDocPlugin)
+///     .add_plugins(DefaultPlugins)
+///     .run();
+/// ```
+#[derive(Resource, Clone, Debug)]
+pub struct RenderDocSettings {
+    /// Bindings that take a capture and open it in the RenderDoc replay UI.
+    ///
+    /// Defaults to `F12` and `Print Screen`, matching RenderDoc's own defaults.
+    pub capture_keys: Vec<KeyBinding>,
+    /// Bindings that cycle which window RenderDoc considers active.
+    ///
+    /// RenderDoc handles these itself, so only bindings to a single
+    /// [`KeyCode`] without modifiers, that RenderDoc can listen to, are supported.
+    /// Defaults to `F11`.
+    pub focus_toggle_keys: Vec<KeyBinding>,
+    /// Bindings that show or hide RenderDoc's overlay, see [`RenderDocOverlay`](crate::RenderDocOverlay).
+    ///
+    /// Defaults to `F10`.
+    pub overlay_toggle_keys: Vec<KeyBinding>,
+    /// The number of frames captured by the capture keys. Defaults to `1`.
+    pub capture_frames: u32,
+    /// How the capture keys capture frames. Defaults to [`CaptureMode::Separate`].
+    ///
+    /// RenderDoc only handles the capture keys itself for single-frame
+    /// [`CaptureMode::Separate`] captures. Otherwise the plugin requests them.
+    ///
+    /// # Examples
+    #[cfg_attr(feature = "capture", doc = "```rust")]
+    #[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+    /// # use bevy::input::{keyboard::KeyboardInput, ButtonState, InputPlugin};
+    /// # use bevy::prelude::*;
+    /// # use bevy_renderdoc::testing::*;
+    /// # use bevy_renderdoc::*;
+    /// #
+    /// let mut app = App::new();
+    /// app.insert_resource(RenderDocSettings {
+    ///     capture_mode: CaptureMode::Scope,
+    ///     ..default()
+    /// })
+    /// .add_plugins(MinimalPlugins)
+    /// .add_plugin(InputPlugin)
+    /// .add_plugin(MockRenderDocPlugin::default());
+    ///
+    /// app.update();
+    /// app.world.send_event(KeyboardInput {
+    ///     scan_code: 0,
+    ///     key_code: Some(KeyCode::F12),
+    ///     state: ButtonState::Pressed,
+    /// });
+    /// app.update();
+    ///
+    /// // RenderDoc doesn't listen to F12, the plugin requests a scope capture instead.
+    /// let mock = app.world.non_send_resource::<MockBackend>();
+    /// assert_eq!(mock.frames_of(&BackendCall::SetCaptureKeys(Vec::new())), vec![0]);
+    /// assert!(mock.triggered_frames().is_empty());
+    ///
+    /// let requests = app.world.resource::<Events<CaptureRequest>>();
+    /// let modes: Vec<_> = requests.get_reader().iter(requests).map(|r| r.mode).collect();
+    /// assert_eq!(modes, vec![CaptureMode::Scope]);
+    /// ```
+    pub capture_mode: CaptureMode,
+    /// The template capture files are named after, relative to the working
+    /// directory unless absolute. Defaults to `renderdoc/bevy_capture`.
+    pub capture_path_template: PathBuf,
+    /// RenderDoc capture options, such as API validation or callstacks.
+    /// Defaults to leaving RenderDoc's own defaults untouched.
+    pub capture_options: CaptureOptions,
+    /// When the capture keys and requests launch the replay UI.
+    /// Defaults to [`ReplayUiPolicy::EveryCapture`].
+    pub replay_ui: ReplayUiPolicy,
+    /// Closes the replay UI launched by the plugin when the application exits.
+    /// Defaults to `false`.
+    pub close_replay_ui_on_exit: bool,
+    /// Frames to capture automatically, counted from `0` at the first update.
+    pub capture_at_frames: Vec<u64>,
+    /// Captures frames automatically after frame-time spikes. Disabled by default.
+    pub spike_capture: Option<SpikeCapture>,
+    /// Ends captures a leaked [`CaptureScope`](crate::CaptureScope) left open
+    /// for too long. Defaults to [`OpenCaptureLimit::default`].
+    pub open_capture_limit: Option<OpenCaptureLimit>,
+    /// Saves the frame capture in progress when the application panics, see
+    /// [`PanicCapture`]. Read when the plugin is added. Disabled by default.
+    pub panic_capture: Option<PanicCapture>,
+    /// Captures the frame after wgpu reports an uncaptured device error, such as
+    /// a validation error, see [`DeviceErrorCapture`].
+    ///
+    /// This replaces wgpu's default handler, so these errors are logged instead
+    /// of panicking. Defaults to [`DeviceErrorCapture::default`].
+    pub capture_on_device_error: Option<DeviceErrorCapture>,
+    /// Writes a `.json` sidecar with application metadata next to every
+    /// capture, and a summary of it into the capture's comments.
+    /// Defaults to `true`.
+    pub write_sidecars: bool,
+    /// Wraps every render graph node in a debug group named after the node,
+    /// and every sub-graph run in a group named after the sub-graph and view,
+    /// so RenderDoc's event browser mirrors the render graph.
+    ///
+    /// Sub-graphs start once the node running them returns, so their groups
+    /// follow that node's group, such as `main/camera_driver`, instead of
+    /// nesting inside it.
+    ///
+    /// Requires [`RenderDocRenderPlugin`](crate::RenderDocRenderPlugin), and
+    /// is read when that plugin is added. Wrapped nodes can no longer be
+    /// looked up by type through `RenderGraph::get_node`, which other plugins
+    /// may rely on. Defaults to `false`.
+    pub debug_groups: bool,
+    /// Inserts [`capture_wgpu_settings`](crate::capture_wgpu_settings) if no
+    /// `WgpuSettings` resource exists when the plugin is added. At startup,
+    /// warns when the final settings use backends or features RenderDoc cannot
+    /// capture, or replaced the inserted ones. Defaults to `true`.
+    pub capture_friendly_wgpu: bool,
+    /// Sets `WINIT_UNIX_BACKEND=x11` on Wayland sessions, so windows are
+    /// created through XWayland, which RenderDoc can capture. Only takes effect
+    /// if the plugin is added before the `WindowPlugin`, and the variable isn't
+    /// set already. See [`DisplayInfo`](crate::DisplayInfo). Defaults to `false`.
+    pub force_x11: bool,
+    /// Panics when [`RenderDocPlugin`](crate::RenderDocPlugin) is added after
+    /// the `RenderPlugin`, or [`RenderDocRenderPlugin`](crate::RenderDocRenderPlugin)
+    /// before it. Otherwise an error is logged and the plugin does nothing.
+    ///
+    /// Use [`with_renderdoc`](crate::RenderDocPluginGroupExt::with_renderdoc)
+    /// to add both plugins in the right order. Defaults to `true`.
+    pub panic_on_misordering: bool,
+    /// The application version recorded in capture metadata.
+    pub app_version: Option<String>,
+    /// The git commit hash recorded in capture metadata.
+    pub git_hash: Option<String>,
+}
+
+#[cfg(feature = "capture")]
+impl RenderDocSettings {
+    /// Returns the button RenderDoc should listen to for `binding`, if RenderDoc
+    /// can take the configured capture on its own.
+    pub(crate) fn native_capture_key(&self, binding: &KeyBinding) -> Option<InputButton> {
+        if self.capture_mode != CaptureMode::Separate || self.capture_frames > 1 {
+            return None;
+        }
+        binding.input_button()
+    }
+}
+
+impl Default for RenderDocSettings {
+    fn default() -> Self {
+        Self {
+            capture_keys: vec![KeyCode::F12.into(), KeyCode::Snapshot.into()],
+            focus_toggle_keys: vec![KeyCode::F11.into()],
+            overlay_toggle_keys: vec![KeyCode::F10.into()],
+            capture_frames: 1,
+            capture_mode: CaptureMode::Separate,
+            capture_path_template: "renderdoc/bevy_capture".into(),
+            capture_options: CaptureOptions::default(),
+            replay_ui: ReplayUiPolicy::EveryCapture,
+            close_replay_ui_on_exit: false,
+            capture_at_frames: Vec::new(),
+            spike_capture: None,
+            open_capture_limit: Some(OpenCaptureLimit::default()),
+            panic_capture: None,
+            capture_on_device_error: Some(DeviceErrorCapture::default()),
+            write_sidecars: true,
+            debug_groups: false,
+            capture_friendly_wgpu: true,
+            force_x11: false,
+            panic_on_misordering: true,
+            app_version: None,
+            git_hash: None,
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy::render::render_resource::{
+    CachedPipelineState, PipelineCache, PipelineDescriptor, Shader, ShaderImport,
+};
+use serde::Serialize;
+
+/// A shader stage of a cached pipeline, with the defs its variant was compiled with.
+#[derive(Clone)]
+pub(crate) struct StageInfo {
+    stage: &'static str,
+    shader: Handle<Shader>,
+    entry_point: String,
+    shader_defs: Vec<String>,
+}
+
+/// A pipeline in the render world's [`PipelineCache`].
+#[derive(Clone)]
+pub(crate) struct PipelineInfo {
+    /// The index of the pipeline in the cache.
+    id: usize,
+    /// The label RenderDoc shows for the pipeline.
+    label: Option<String>,
+    state: &'static str,
+    stages: Vec<StageInfo>,
+}
+
+/// The pipelines the render world compiled, shared with the main world so they
+/// can be written to capture metadata.
+#[derive(Resource, Clone, Default)]
+pub(crate) struct ShaderPipelines(Arc<Mutex<Vec<PipelineInfo>>>);
+
+/// A [`PipelineInfo`] with its shaders resolved to file or import paths.
+#[derive(Serialize)]
+pub(crate) struct PipelineSidecar {
+    id: usize,
+    label: Option<String>,
+    state: &'static str,
+    stages: Vec<StageSidecar>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct StageSidecar {
+    stage: &'static str,
+    shader: String,
+    entry_point: String,
+    shader_defs: Vec<String>,
+}
+
+impl ShaderPipelines {
+    /// Returns the recorded pipelines, resolving shader handles through `world`.
+    pub(crate) fn sidecar(&self, world: &World) -> Vec<PipelineSidecar> {
+        let pipelines = match self.0.lock() {
+            Ok(pipelines) => pipelines,
+            Err(_) => return Vec::new(),
+        };
+
+        pipelines
+            .iter()
+            .map(|pipeline| PipelineSidecar {
+                id: pipeline.id,
+                label: pipeline.label.clone(),
+                state: pipeline.state,
+                stages: pipeline
+                    .stages
+                    .iter()
+                    .map(|stage| StageSidecar {
+                        stage: stage.stage,
+                        shader: shader_name(world, &stage.shader),
+                        entry_point: stage.entry_point.clone(),
+                        shader_defs: stage.shader_defs.clone(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Returns the asset path of `shader`, or its import path for shaders that
+/// weren't loaded from a file, such as bevy's built-in ones.
+fn shader_name(world: &World, shader: &Handle<Shader>) -> String {
+    let asset_path = world
+        .get_resource::<AssetServer>()
+        .and_then(|server| server.get_handle_path(shader))
+        .map(|path| path.path().display().to_string());
+    if let Some(path) = asset_path {
+        return path;
+    }
+
+    let import_path = world
+        .get_resource::<Assets<Shader>>()
+        .and_then(|shaders| shaders.get(shader))
+        .and_then(|shader| shader.import_path().cloned());
+    match import_path {
+        Some(ShaderImport::AssetPath(path) | ShaderImport::Custom(path)) => path,
+        None => format!("{:?}", shader.id()),
+    }
+}
+
+/// Records the pipelines in the cache whenever one was added or compiled.
+pub(crate) fn record_pipelines(
+    cache: Res<PipelineCache>,
+    pipelines: Res<ShaderPipelines>,
+    mut recorded: Local<(usize, usize)>,
+) {
+    let compiled = cache
+        .pipelines()
+        .filter(|pipeline| !matches!(pipeline.state, CachedPipelineState::Queued))
+        .count();
+    let current = (cache.pipelines().count(), compiled);
+    if *recorded == current {
+        return;
+    }
+    *recorded = current;
+
+    let infos = cache
+        .pipelines()
+        .enumerate()
+        .map(|(id, pipeline)| {
+            let state = match pipeline.state {
+                CachedPipelineState::Queued => "queued",
+                CachedPipelineState::Ok(_) => "ok",
+                CachedPipelineState::Err(_) => "error",
+            };
+            let (label, stages) = match &pipeline.descriptor {
+                PipelineDescriptor::RenderPipelineDescriptor(descriptor) => {
+                    let vertex = &descriptor.vertex;
+                    let mut stages = vec![StageInfo {
+                        stage: "vertex",
+                        shader: vertex.shader.clone_weak(),
+                        entry_point: vertex.entry_point.to_string(),
+                        shader_defs: vertex.shader_defs.clone(),
+                    }];
+                    stages.extend(descriptor.fragment.iter().map(|fragment| StageInfo {
+                        stage: "fragment",
+                        shader: fragment.shader.clone_weak(),
+                        entry_point: fragment.entry_point.to_string(),
+                        shader_defs: fragment.shader_defs.clone(),
+                    }));
+                    (&descriptor.label, stages)
+                }
+                PipelineDescriptor::ComputePipelineDescriptor(descriptor) => {
+                    let stages = vec![StageInfo {
+                        stage: "compute",
+                        shader: descriptor.shader.clone_weak(),
+                        entry_point: descriptor.entry_point.to_string(),
+                        shader_defs: descriptor.shader_defs.clone(),
+                    }];
+                    (&descriptor.label, stages)
+                }
+            };
+
+            PipelineInfo {
+                id,
+                label: label.as_ref().map(|label| label.to_string()),
+                state,
+                stages,
+            }
+        })
+        .collect();
+
+    if let Ok(mut pipelines) = pipelines.0.lock() {
+        *pipelines = infos;
+    }
+}
@@ -0,0 +1,287 @@
+#[cfg(feature = "capture")]
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[cfg(feature = "capture")]
+use bevy::prelude::*;
+
+#[cfg(feature = "capture")]
+use crate::capture::CaptureTracker;
+#[cfg(feature = "capture")]
+use crate::{CaptureBackend, CaptureRequest, RenderDocSettings};
+
+/// Configuration for capturing frames automatically after a frame-time spike.
+///
+/// Enable it by setting [`RenderDocSettings::spike_capture`]. Frames that were
+/// captured, which capturing itself slows down, are neither checked nor part
+/// of the median.
+///
+/// # Examples
+/// ```rust, no_run
+/// # use std::time::Duration;
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// App::new()
+///     .insert_resource(RenderDocSettings {
+///         spike_capture: Some(SpikeCapture {
+///             budget: Some(Duration::from_millis(33)),
+///             ..default()
+///         }),
+///         ..default()
+///     })
+///     .add_plugin(RenderDocPlugin)
+///     .add_plugins(DefaultPlugins)
+///     .run();
+/// ```
+///
+/// Driving the frame times by hand:
+#[cfg_attr(feature = "capture", doc = "```rust")]
+#[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+/// # use std::time::Duration;
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let mut app = App::new();
+/// app.insert_resource(RenderDocSettings {
+///     spike_capture: Some(SpikeCapture {
+///         budget: Some(Duration::from_millis(50)),
+///         median_factor: None,
+///         warmup: Duration::from_millis(500),
+///         cooldown: Duration::ZERO,
+///         ..default()
+///     }),
+///     ..default()
+/// })
+/// .init_resource::<Time>()
+/// .add_plugin(MockRenderDocPlugin::default());
+///
+/// let mut run_frame = |app: &mut App, millis: u64| {
+///     let mut time = app.world.resource_mut::<Time>();
+///     let last = time.last_update().unwrap_or_else(|| time.startup());
+///     time.update_with_instant(last + Duration::from_millis(millis));
+///     app.update();
+/// };
+///
+/// // Compiling shaders while warming up.
+/// run_frame(&mut app, 0);
+/// run_frame(&mut app, 300);
+/// for _ in 0..20 {
+///     run_frame(&mut app, 16);
+/// }
+/// // A spike, then the slow frames of its own capture.
+/// run_frame(&mut app, 80);
+/// for millis in [200, 120, 16, 16] {
+///     run_frame(&mut app, millis);
+/// }
+///
+/// let mock = app.world.non_send_resource::<MockBackend>();
+/// assert_eq!(mock.triggered_frames(), vec![22]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SpikeCapture {
+    /// Frames taking longer than this count as a spike.
+    pub budget: Option<Duration>,
+    /// Frames taking longer than this multiple of the median frame time
+    /// count as a spike. Defaults to `3.0`.
+    pub median_factor: Option<f32>,
+    /// The number of recent frames the median is taken over. Defaults to `120`.
+    pub window: usize,
+    /// The number of frames captured after a spike. Defaults to `1`.
+    pub frames: u32,
+    /// The time after startup during which frames aren't checked, since
+    /// compiling shaders makes the first frames slow. Defaults to 5 seconds.
+    pub warmup: Duration,
+    /// The minimum time between two spike captures. Defaults to 10 seconds.
+    pub cooldown: Duration,
+    /// The maximum number of spike captures per session. Defaults to `5`.
+    pub max_captures: u32,
+}
+
+impl Default for SpikeCapture {
+    fn default() -> Self {
+        Self {
+            budget: None,
+            median_factor: Some(3.0),
+            window: 120,
+            frames: 1,
+            warmup: Duration::from_secs(5),
+            cooldown: Duration::from_secs(10),
+            max_captures: 5,
+        }
+    }
+}
+
+/// The durations of the most recent frames, oldest first.
+#[cfg(feature = "capture")]
+#[derive(Resource, Default)]
+pub(crate) struct FrameTimes {
+    times: VecDeque<FrameTime>,
+}
+
+#[cfg(feature = "capture")]
+#[derive(Clone, Copy)]
+struct FrameTime {
+    duration: Duration,
+    /// Whether the frame was captured, which makes it slower than usual.
+    captured: bool,
+}
+
+#[cfg(feature = "capture")]
+impl FrameTimes {
+    /// The number of frames kept, enough for a few seconds at high frame rates.
+    const CAPACITY: usize = 1024;
+
+    /// Returns the most recent frame times adding up to at least `span`, newest first.
+    pub(crate) fn recent(&self, span: Duration) -> impl Iterator<Item = Duration> + '_ {
+        let mut total = Duration::ZERO;
+        self.times
+            .iter()
+            .rev()
+            .map(|frame_time| frame_time.duration)
+            .take_while(move |&frame_time| {
+                let included = total < span;
+                total += frame_time;
+                included
+            })
+    }
+
+    /// Returns the duration of the latest frame, unless it was captured.
+    fn latest(&self) -> Option<Duration> {
+        self.times
+            .back()
+            .filter(|frame_time| !frame_time.captured)
+            .map(|frame_time| frame_time.duration)
+    }
+
+    /// Returns the median of the `window` uncaptured frames before the latest
+    /// one, so a spike doesn't skew the median it is compared against.
+    fn previous_median(&self, window: usize) -> Option<Duration> {
+        if window == 0 {
+            return None;
+        }
+
+        let mut previous: Vec<_> = self
+            .times
+            .iter()
+            .rev()
+            .skip(1)
+            .filter(|frame_time| !frame_time.captured)
+            .map(|frame_time| frame_time.duration)
+            .take(window)
+            .collect();
+        if previous.len() < window {
+            return None;
+        }
+        previous.sort_unstable();
+        Some(previous[window / 2])
+    }
+}
+
+#[cfg(feature = "capture")]
+pub(crate) fn record_frame_times<B: CaptureBackend>(
+    time: Option<Res<Time>>,
+    rd: NonSend<B>,
+    tracker: Res<CaptureTracker>,
+    mut frame_times: ResMut<FrameTimes>,
+) {
+    let delta = match time {
+        Some(time) => time.raw_delta(),
+        None => return,
+    };
+
+    // The first update has no previous frame to measure.
+    if delta.is_zero() {
+        return;
+    }
+
+    if frame_times.times.len() == FrameTimes::CAPACITY {
+        frame_times.times.pop_front();
+    }
+    // The delta measures the previous frame.
+    let captured = rd.is_frame_capturing()
+        || tracker
+            .frame()
+            .checked_sub(1)
+            .is_some_and(|frame| tracker.was_capturing(frame));
+    frame_times.times.push_back(FrameTime {
+        duration: delta,
+        captured,
+    });
+}
+
+/// Automatic captures taken so far, to space them out and cap them per session.
+#[cfg(feature = "capture")]
+#[derive(Default)]
+pub(crate) struct CaptureBudget {
+    captures: u32,
+    last_capture: Option<Duration>,
+}
+
+#[cfg(feature = "capture")]
+impl CaptureBudget {
+    /// Returns whether another capture may be taken at `now`, measured since startup.
+    pub(crate) fn allows(&self, now: Duration, cooldown: Duration, max_captures: u32) -> bool {
+        self.captures < max_captures && self.last_capture.is_none_or(|last| now >= last + cooldown)
+    }
+
+    /// Records a capture taken at `now`.
+    pub(crate) fn record(&mut self, now: Duration) {
+        self.captures += 1;
+        self.last_capture = Some(now);
+    }
+}
+
+#[cfg(feature = "capture")]
+pub(crate) fn capture_spikes(
+    settings: Res<RenderDocSettings>,
+    time: Option<Res<Time>>,
+    frame_times: Res<FrameTimes>,
+    mut budget: Local<CaptureBudget>,
+    mut requests: EventWriter<CaptureRequest>,
+) {
+    let (config, time) = match (&settings.spike_capture, time) {
+        (Some(config), Some(time)) => (config, time),
+        _ => return,
+    };
+
+    let now = time.raw_elapsed();
+    if now < config.warmup || !budget.allows(now, config.cooldown, config.max_captures) {
+        return;
+    }
+
+    let frame_time = match frame_times.latest() {
+        Some(frame_time) => frame_time,
+        None => return,
+    };
+
+    let over_budget = matches!(config.budget, Some(budget) if frame_time > budget);
+    let median = config.median_factor.and_then(|factor| {
+        frame_times
+            .previous_median(config.window)
+            .map(|median| (factor, median))
+    });
+    let over_median = matches!(median, Some((factor, median)) if frame_time > median.mul_f32(factor));
+
+    if !over_budget && !over_median {
+        return;
+    }
+
+    let mut comment = format!("Frame time spike: {:.2} ms", frame_time.as_secs_f64() * 1000.0);
+    if let Some((_, median)) = median {
+        comment += &format!(
+            " ({:.1}x the {:.2} ms median)",
+            frame_time.as_secs_f64() / median.as_secs_f64(),
+            median.as_secs_f64() * 1000.0
+        );
+    }
+
+    info!("Capturing RenderDoc frame after a spike. {}", comment);
+    budget.record(now);
+    requests.send(CaptureRequest {
+        frames: Some(config.frames),
+        comment: Some(comment),
+        ..default()
+    });
+}
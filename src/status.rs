@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+
+use crate::DisplayInfo;
+
+#[cfg(feature = "capture")]
+use crate::{CaptureBackend, RenderDocCapabilities};
+
+/// A snapshot of RenderDoc's state, updated at the start of every frame.
+///
+/// Unlike [`RenderDocResource`](crate::RenderDocResource), this can be read
+/// from any system, including run conditions, without calling into RenderDoc.
+///
+/// # Examples
+#[cfg_attr(feature = "capture", doc = "```rust")]
+#[cfg_attr(not(feature = "capture"), doc = "```rust, ignore")]
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let mut app = App::new();
+/// app.add_plugins(MinimalPlugins)
+///     .add_plugin(MockRenderDocPlugin::default());
+///
+/// app.world.send_event(CaptureRequest::default());
+/// app.update();
+/// app.update();
+///
+/// let status = app.world.resource::<RenderDocStatus>();
+/// assert!(status.loaded);
+/// assert_eq!(status.api_version, Some((1, 6, 0)));
+/// assert_eq!(status.num_captures, 1);
+/// ```
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenderDocStatus {
+    /// Whether RenderDoc has been loaded.
+    pub loaded: bool,
+    /// The API version the library reports, if loaded.
+    pub api_version: Option<(u32, u32, u32)>,
+    /// Why RenderDoc isn't loaded, if it isn't.
+    pub load_error: Option<String>,
+    /// Whether a frame capture is in progress.
+    pub capturing: bool,
+    /// The number of captures taken so far.
+    pub num_captures: u32,
+    /// Whether a replay UI is connected to the application.
+    pub replay_ui_connected: bool,
+    /// The display server windows are created on, detected on Linux once
+    /// RenderDoc has been loaded.
+    pub display: Option<DisplayInfo>,
+}
+
+impl RenderDocStatus {
+    /// Returns the status of an application RenderDoc failed to load into.
+    pub(crate) fn failed(error: impl Into<String>) -> Self {
+        Self {
+            load_error: Some(error.into()),
+            ..default()
+        }
+    }
+}
+
+#[cfg(feature = "capture")]
+pub(crate) fn update_status<B: CaptureBackend>(
+    capabilities: Res<RenderDocCapabilities>,
+    mut rd: NonSendMut<B>,
+    mut status: ResMut<RenderDocStatus>,
+) {
+    let current = RenderDocStatus {
+        loaded: true,
+        api_version: Some(capabilities.api_version),
+        load_error: None,
+        capturing: rd.is_frame_capturing(),
+        num_captures: rd.get_num_captures(),
+        replay_ui_connected: rd.is_target_control_connected(),
+        display: status.display.clone(),
+    };
+
+    // Only trigger change detection when something changed.
+    if *status != current {
+        *status = current;
+    }
+}
@@ -0,0 +1,455 @@
+//! Helpers for testing code that uses [`RenderDocPlugin`](crate::RenderDocPlugin)
+//! without RenderDoc being installed.
+//!
+//! [`MockRenderDocPlugin`] runs the same systems as the real plugin, but backed
+//! by a [`MockBackend`] that records every call it receives together with the
+//! frame it was made on.
+//!
+//! # Examples
+//! ```rust
+//! use bevy::input::{keyboard::KeyboardInput, ButtonState, InputPlugin};
+//! use bevy::prelude::*;
+//! use bevy_renderdoc::testing::*;
+//! use bevy_renderdoc::*;
+//!
+//! let mut app = App::new();
+//! app.insert_resource(RenderDocSettings {
+//!     capture_keys: vec![KeyBinding::new(ScanCode(88))],
+//!     ..default()
+//! })
+//! .add_plugins(MinimalPlugins)
+//! .add_plugin(InputPlugin)
+//! .add_plugin(MockRenderDocPlugin::default());
+//!
+//! app.update();
+//! app.world.send_event(KeyboardInput {
+//!     scan_code: 88,
+//!     key_code: None,
+//!     state: ButtonState::Pressed,
+//! });
+//! app.update();
+//!
+//! let mock = app.world.non_send_resource::<MockBackend>();
+//! assert_eq!(mock.triggered_frames(), vec![1]);
+//! assert_eq!(mock.get_num_captures(), 1);
+//! ```
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use bevy::prelude::*;
+use renderdoc::{CaptureOption, DevicePointer, InputButton, OverlayBits, WindowHandle};
+
+use crate::device_error::DeviceErrors;
+use crate::env::Overrides;
+use crate::panic_capture::{self, PanicFrame};
+use crate::{CaptureBackend, PanicCapture, RenderDocCapabilities, RenderDocSystem};
+
+/// A call received by a [`MockBackend`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BackendCall {
+    /// [`CaptureBackend::trigger_capture`]
+    TriggerCapture,
+    /// [`CaptureBackend::trigger_multi_frame_capture`]
+    TriggerMultiFrameCapture(u32),
+    /// [`CaptureBackend::start_frame_capture`]
+    StartFrameCapture,
+    /// [`CaptureBackend::end_frame_capture`]
+    EndFrameCapture,
+    /// [`CaptureBackend::discard_frame_capture`]
+    DiscardFrameCapture,
+    /// [`CaptureBackend::set_active_window`]
+    SetActiveWindow,
+    /// [`CaptureBackend::set_capture_file_comments`]
+    SetCaptureFileComments {
+        /// The capture the comments were set on.
+        path: Option<PathBuf>,
+        /// The comments.
+        comments: String,
+    },
+    /// [`CaptureBackend::set_capture_title`]
+    SetCaptureTitle(String),
+    /// [`CaptureBackend::launch_replay_ui`]
+    LaunchReplayUi {
+        /// Whether the UI should connect to the application immediately.
+        connect_immediately: bool,
+        /// Extra command-line options passed to the UI.
+        extra_opts: Option<String>,
+    },
+    /// [`CaptureBackend::show_replay_ui`]
+    ShowReplayUi,
+    /// [`CaptureBackend::set_capture_option`]
+    SetCaptureOption(CaptureOption, u32),
+    /// [`CaptureBackend::set_capture_file_path_template`]
+    SetCaptureFilePathTemplate(PathBuf),
+    /// [`CaptureBackend::mask_overlay_bits`]
+    MaskOverlayBits {
+        /// Bits that are kept.
+        and: OverlayBits,
+        /// Bits that are enabled.
+        or: OverlayBits,
+    },
+    /// [`CaptureBackend::set_capture_keys`]
+    SetCaptureKeys(Vec<InputButton>),
+    /// [`CaptureBackend::set_focus_toggle_keys`]
+    SetFocusToggleKeys(Vec<InputButton>),
+}
+
+/// The process id [`MockBackend`] reports for the replay UI by default.
+///
+/// Larger than any Linux process id, and odd, which Windows process ids never are.
+pub const MOCK_REPLAY_UI_PID: u32 = i32::MAX as u32;
+
+/// A [`CaptureBackend`] that records calls instead of talking to RenderDoc.
+///
+/// Triggered captures are written to the capture list when the frame they were
+/// requested on ends, mirroring RenderDoc which captures the next presented frame.
+/// Multi-frame captures add one entry per following frame. No files are written
+/// to disk.
+#[derive(Clone, Debug)]
+pub struct MockBackend {
+    /// Every call received so far, with the frame it was received on.
+    pub calls: Vec<(u64, BackendCall)>,
+    /// The captures "written" so far.
+    pub captures: Vec<(PathBuf, SystemTime)>,
+    /// The process id reported by [`CaptureBackend::launch_replay_ui`], or
+    /// [`None`] to simulate a launch failure.
+    ///
+    /// Defaults to [`MOCK_REPLAY_UI_PID`], which no process has, so the plugin
+    /// finds the replay UI closed again right away. Set
+    /// [`target_control_connected`](Self::target_control_connected) to
+    /// simulate a replay UI that stays open.
+    pub replay_ui_pid: Option<u32>,
+    /// Whether [`CaptureBackend::is_target_control_connected`] reports a
+    /// connected replay UI. Defaults to `false`.
+    pub target_control_connected: bool,
+    /// The API version the backend reports, which decides its
+    /// [`capabilities`](CaptureBackend::capabilities). Defaults to `(1, 6, 0)`.
+    pub api_version: (u32, u32, u32),
+    frame: u64,
+    pending_frames: u32,
+    capturing: bool,
+    scope_started: Option<Instant>,
+    template: PathBuf,
+    overlay_bits: OverlayBits,
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self {
+            calls: Vec::new(),
+            captures: Vec::new(),
+            replay_ui_pid: Some(MOCK_REPLAY_UI_PID),
+            target_control_connected: false,
+            api_version: (1, 6, 0),
+            frame: 0,
+            pending_frames: 0,
+            capturing: false,
+            scope_started: None,
+            template: PathBuf::new(),
+            overlay_bits: OverlayBits::DEFAULT,
+        }
+    }
+}
+
+impl MockBackend {
+    /// Returns the current frame, counted from `0` at the first update.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns the frames [`CaptureBackend::trigger_capture`] was called on.
+    pub fn triggered_frames(&self) -> Vec<u64> {
+        self.frames_of(&BackendCall::TriggerCapture)
+    }
+
+    /// Returns the frames `call` was received on.
+    pub fn frames_of(&self, call: &BackendCall) -> Vec<u64> {
+        self.calls
+            .iter()
+            .filter(|(_, c)| c == call)
+            .map(|(frame, _)| *frame)
+            .collect()
+    }
+
+    /// Ends the current frame, writing the capture triggered for it, if any.
+    pub fn end_frame(&mut self) {
+        if self.pending_frames > 0 {
+            self.pending_frames -= 1;
+            self.write_capture();
+        }
+        self.frame += 1;
+    }
+
+    fn record(&mut self, call: BackendCall) {
+        self.calls.push((self.frame, call));
+    }
+
+    fn write_capture(&mut self) {
+        let path = format!("{}_frame{}.rdc", self.template.display(), self.frame);
+        self.captures.push((path.into(), SystemTime::now()));
+    }
+}
+
+impl CaptureBackend for MockBackend {
+    fn capabilities(&self) -> RenderDocCapabilities {
+        RenderDocCapabilities::for_version(self.api_version, self.api_version)
+    }
+
+    fn trigger_capture(&mut self) {
+        self.record(BackendCall::TriggerCapture);
+        self.pending_frames = self.pending_frames.max(1);
+    }
+
+    fn trigger_multi_frame_capture(&mut self, frames: u32) {
+        self.record(BackendCall::TriggerMultiFrameCapture(frames));
+        self.pending_frames = self.pending_frames.max(frames);
+    }
+
+    fn start_frame_capture(&mut self, _device: DevicePointer, _window: WindowHandle) {
+        self.record(BackendCall::StartFrameCapture);
+        self.capturing = true;
+    }
+
+    fn end_frame_capture(&mut self, _device: DevicePointer, _window: WindowHandle) {
+        self.record(BackendCall::EndFrameCapture);
+        if std::mem::take(&mut self.capturing) {
+            self.write_capture();
+        }
+    }
+
+    fn discard_frame_capture(&mut self, _device: DevicePointer, _window: WindowHandle) -> bool {
+        self.record(BackendCall::DiscardFrameCapture);
+        std::mem::take(&mut self.capturing)
+    }
+
+    fn set_active_window(&mut self, _device: DevicePointer, _window: WindowHandle) {
+        self.record(BackendCall::SetActiveWindow);
+    }
+
+    fn is_frame_capturing(&self) -> bool {
+        self.capturing
+    }
+
+    fn scope_started(&self) -> Option<Instant> {
+        self.scope_started
+    }
+
+    fn set_scope_started(&mut self, started: Option<Instant>) {
+        self.scope_started = started;
+    }
+
+    fn get_num_captures(&self) -> u32 {
+        self.captures.len() as u32
+    }
+
+    fn get_capture(&self, index: u32) -> Option<(PathBuf, SystemTime)> {
+        self.captures.get(index as usize).cloned()
+    }
+
+    fn set_capture_file_comments(&mut self, path: Option<&Path>, comments: &str) -> bool {
+        self.record(BackendCall::SetCaptureFileComments {
+            path: path.map(Path::to_owned),
+            comments: comments.to_owned(),
+        });
+        self.capabilities().capture_comments
+    }
+
+    fn set_capture_title(&mut self, title: &str) -> bool {
+        self.record(BackendCall::SetCaptureTitle(title.to_owned()));
+        self.capabilities().capture_title
+    }
+
+    fn launch_replay_ui(&mut self, connect_immediately: bool, extra_opts: Option<&str>) -> Option<u32> {
+        self.record(BackendCall::LaunchReplayUi {
+            connect_immediately,
+            extra_opts: extra_opts.map(str::to_owned),
+        });
+        self.replay_ui_pid
+    }
+
+    fn is_target_control_connected(&mut self) -> bool {
+        self.target_control_connected
+    }
+
+    fn show_replay_ui(&mut self) -> bool {
+        self.record(BackendCall::ShowReplayUi);
+        self.capabilities().show_replay_ui && self.target_control_connected
+    }
+
+    fn set_capture_option(&mut self, option: CaptureOption, value: u32) -> bool {
+        self.record(BackendCall::SetCaptureOption(option, value));
+        true
+    }
+
+    fn get_capture_file_path_template(&self) -> PathBuf {
+        self.template.clone()
+    }
+
+    fn set_capture_file_path_template(&mut self, template: &Path) {
+        self.record(BackendCall::SetCaptureFilePathTemplate(template.to_owned()));
+        self.template = template.to_owned();
+    }
+
+    fn get_overlay_bits(&self) -> OverlayBits {
+        self.overlay_bits
+    }
+
+    fn mask_overlay_bits(&mut self, and: OverlayBits, or: OverlayBits) {
+        self.record(BackendCall::MaskOverlayBits { and, or });
+        self.overlay_bits = (self.overlay_bits & and) | or;
+    }
+
+    fn set_capture_keys(&mut self, keys: &[InputButton]) {
+        self.record(BackendCall::SetCaptureKeys(keys.to_vec()));
+    }
+
+    fn set_focus_toggle_keys(&mut self, keys: &[InputButton]) {
+        self.record(BackendCall::SetFocusToggleKeys(keys.to_vec()));
+    }
+}
+
+/// A [`RenderDocPlugin`](crate::RenderDocPlugin) backed by a [`MockBackend`].
+///
+/// The backend is available as a [`NonSend`] resource, and its frame counter
+/// advances at the end of every update.
+///
+/// # Examples
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let mut app = App::new();
+/// app.add_plugins(MinimalPlugins)
+///     .add_plugin(MockRenderDocPlugin::default());
+///
+/// app.world.send_event(CaptureRequest {
+///     frames: Some(2),
+///     ..default()
+/// });
+/// app.update();
+/// app.update();
+///
+/// let completed = app.world.resource::<Events<CaptureCompleted>>();
+/// let frames: Vec<u64> = completed.get_reader().iter(completed).map(|c| c.frame).collect();
+/// assert_eq!(frames, vec![0, 1]);
+/// ```
+///
+/// Environment overrides, see [`RenderDocPlugin`](crate::RenderDocPlugin#environment),
+/// are read from [`env`](Self::env) and [`args`](Self::args) instead of the process:
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let mut args = vec!["--renderdoc-capture-frame".into(), "7".into(), "--renderdoc-capture-frame=3".into()];
+/// # #[cfg(unix)]
+/// # {
+/// // Arguments that aren't valid Unicode are skipped.
+/// use std::os::unix::ffi::OsStringExt;
+/// args.insert(0, std::ffi::OsString::from_vec(vec![0xff, 0xfe]));
+/// # }
+///
+/// let mut app = App::new();
+/// app.add_plugins(MinimalPlugins)
+///     .add_plugin(MockRenderDocPlugin {
+///         env: vec![("BEVY_RENDERDOC_CAPTURE_FRAMES".into(), " 120, 3,,x".into())],
+///         args,
+///         ..default()
+///     });
+///
+/// let settings = app.world.resource::<RenderDocSettings>();
+/// assert_eq!(settings.capture_at_frames, vec![3, 7, 120]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MockRenderDocPlugin {
+    /// The backend the plugin starts out with.
+    pub backend: MockBackend,
+    /// Environment variables the plugin reads instead of the process environment.
+    pub env: Vec<(String, String)>,
+    /// Command-line arguments the plugin reads instead of the process
+    /// arguments, without the program name.
+    pub args: Vec<OsString>,
+}
+
+impl Plugin for MockRenderDocPlugin {
+    fn build(&self, app: &mut App) {
+        let var = |name: &str| {
+            self.env
+                .iter()
+                .find(|(var, _)| var == name)
+                .map(|(_, value)| OsString::from(value))
+        };
+        Overrides::read_from(var, self.args.clone()).insert(app);
+
+        crate::build_with_backend(app, self.backend.clone());
+        app.add_system_to_stage(
+            CoreStage::Last,
+            end_mock_frame.before(RenderDocSystem::Completion),
+        );
+    }
+}
+
+fn end_mock_frame(mut mock: NonSendMut<MockBackend>) {
+    mock.end_frame();
+}
+
+/// Runs what the panic hook installed for [`PanicCapture`] does when the
+/// application panics with `message` on `frame`, against `backend`.
+///
+/// Writes the report, and the marker if requested, next to the backend's
+/// capture path template.
+///
+/// # Examples
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_renderdoc::testing::*;
+/// # use bevy_renderdoc::*;
+/// #
+/// let dir = std::env::temp_dir().join(format!("bevy_renderdoc_panic_{}", std::process::id()));
+/// let mut mock = MockBackend::default();
+/// mock.set_capture_file_path_template(&dir.join("capture"));
+///
+/// let scope = CaptureScope::begin(&mut mock);
+/// std::mem::forget(scope);
+/// let config = PanicCapture {
+///     backtrace: false,
+///     capture_next_launch: true,
+/// };
+/// simulate_panic(&mut mock, &config, 7, "out of bounds");
+///
+/// // The capture in progress is saved with the panic in its comments.
+/// assert!(!mock.is_frame_capturing());
+/// assert_eq!(mock.get_num_captures(), 1);
+/// assert!(mock.calls.contains(&(
+///     0,
+///     BackendCall::SetCaptureFileComments {
+///         path: Some(dir.join("capture_frame0.rdc")),
+///         comments: "Panicked: out of bounds".into(),
+///     },
+/// )));
+///
+/// let report = std::fs::read_to_string(dir.join("last_panic.json")).unwrap();
+/// assert!(report.contains(r#""message": "out of bounds""#));
+/// assert!(report.contains(r#""frame": 7"#));
+/// assert!(report.contains("capture_frame0.rdc"));
+/// let marker = std::fs::read_to_string(dir.join("capture_next_launch")).unwrap();
+/// assert_eq!(marker, "Panicked: out of bounds");
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn simulate_panic(backend: &mut MockBackend, config: &PanicCapture, frame: u64, message: &str) {
+    let panic_frame = PanicFrame::default();
+    panic_frame.0.store(frame, std::sync::atomic::Ordering::Relaxed);
+    panic_capture::save_capture(backend, config, &panic_frame, message.to_owned(), None);
+}
+
+/// Reports `error` as if wgpu's uncaptured error handler received it, for
+/// [`DeviceErrorCapture`](crate::DeviceErrorCapture).
+///
+/// Does nothing if `app` has no [`MockRenderDocPlugin`].
+pub fn report_device_error(app: &mut App, error: impl Into<String>) {
+    if let Some(errors) = app.world.get_resource::<DeviceErrors>() {
+        errors.report(error.into());
+    }
+}
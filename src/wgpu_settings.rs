@@ -0,0 +1,137 @@
+use bevy::render::settings::{Backends, PowerPreference, WgpuFeatures, WgpuSettings};
+#[cfg(feature = "capture")]
+use bevy::prelude::*;
+
+/// Features RenderDoc cannot capture reliably.
+///
+/// Writes to persistently mapped device memory have to be diffed by RenderDoc
+/// on every submit, which is slow and misses writes racing the GPU.
+const UNCAPTURABLE_FEATURES: WgpuFeatures = WgpuFeatures::MAPPABLE_PRIMARY_BUFFERS;
+
+/// Returns the backends RenderDoc can capture on this platform.
+fn capturable_backends() -> Backends {
+    if cfg!(windows) {
+        Backends::DX12 | Backends::DX11 | Backends::VULKAN | Backends::GL
+    } else if cfg!(any(target_os = "macos", target_os = "ios", target_arch = "wasm32")) {
+        Backends::empty()
+    } else {
+        Backends::VULKAN | Backends::GL
+    }
+}
+
+/// Returns [`WgpuSettings`] that RenderDoc can capture.
+///
+/// Starts from bevy's defaults, including the `WGPU_BACKEND` environment
+/// variable, and keeps only the backends RenderDoc hooks on this platform:
+/// Vulkan and GL on Linux, plus DirectX on Windows. Features RenderDoc cannot
+/// capture are disabled, and the high-performance adapter is preferred.
+///
+/// [`RenderDocPlugin`](crate::RenderDocPlugin) inserts these settings unless
+/// a [`WgpuSettings`] resource already exists, see
+/// [`RenderDocSettings::capture_friendly_wgpu`](crate::RenderDocSettings::capture_friendly_wgpu).
+///
+/// # Examples
+/// ```rust
+/// # use bevy::render::settings::{Backends, WgpuSettings};
+/// # use bevy_renderdoc::*;
+/// #
+/// let settings = WgpuSettings {
+///     device_label: Some("game".into()),
+///     ..capture_wgpu_settings()
+/// };
+/// # #[cfg(target_os = "linux")]
+/// assert!(!settings.backends.unwrap().contains(Backends::DX12));
+/// ```
+pub fn capture_wgpu_settings() -> WgpuSettings {
+    let mut settings = WgpuSettings {
+        power_preference: PowerPreference::HighPerformance,
+        ..WgpuSettings::default()
+    };
+
+    if let Some(backends) = settings.backends {
+        let capturable = backends & capturable_backends();
+        // Keep an explicit WGPU_BACKEND RenderDoc can't capture, it is reported instead.
+        if !capturable.is_empty() {
+            settings.backends = Some(capturable);
+        }
+    }
+
+    settings.features.remove(UNCAPTURABLE_FEATURES);
+    let disabled = settings.disabled_features.unwrap_or(WgpuFeatures::empty());
+    settings.disabled_features = Some(disabled | UNCAPTURABLE_FEATURES);
+    settings
+}
+
+/// Returns why RenderDoc won't be able to capture with `settings`.
+#[cfg(feature = "capture")]
+fn conflicts(settings: &WgpuSettings) -> Vec<String> {
+    let mut conflicts = Vec::new();
+
+    let capturable = capturable_backends();
+    if let Some(backends) = settings.backends {
+        if !capturable.is_empty() && !backends.intersects(capturable) {
+            conflicts.push(format!(
+                "WgpuSettings only enable the {:?} backends, RenderDoc captures {:?} on this platform",
+                backends, capturable
+            ));
+        }
+    }
+
+    let uncapturable = settings.features & UNCAPTURABLE_FEATURES;
+    let disabled = settings.disabled_features.unwrap_or(WgpuFeatures::empty());
+    if !uncapturable.is_empty() && !disabled.contains(uncapturable) {
+        conflicts.push(format!(
+            "WgpuSettings enable {:?}, which RenderDoc cannot capture reliably",
+            uncapturable
+        ));
+    }
+
+    conflicts
+}
+
+/// The settings [`configure`] inserted, to tell whether the application
+/// replaced them afterwards.
+#[cfg(feature = "capture")]
+#[derive(Resource)]
+struct InsertedSettings(WgpuSettings);
+
+/// Inserts [`capture_wgpu_settings`] unless the application provides its own
+/// settings, and warns about settings RenderDoc can't capture with.
+#[cfg(feature = "capture")]
+pub(crate) fn configure(app: &mut App) {
+    if !app.world.contains_resource::<WgpuSettings>() {
+        let settings = capture_wgpu_settings();
+        app.insert_resource(InsertedSettings(settings.clone()))
+            .insert_resource(settings);
+    }
+    // Settings inserted after the plugin was added are only known at startup.
+    app.add_startup_system(check_settings);
+}
+
+/// Warns about the final [`WgpuSettings`] if RenderDoc can't capture with
+/// them, or if they replaced the settings [`configure`] inserted.
+#[cfg(feature = "capture")]
+fn check_settings(settings: Option<Res<WgpuSettings>>, inserted: Option<Res<InsertedSettings>>) {
+    let settings = match settings {
+        Some(settings) => settings,
+        None => return,
+    };
+
+    if let Some(inserted) = inserted {
+        let inserted = &inserted.0;
+        let replaced = settings.backends != inserted.backends
+            || settings.power_preference != inserted.power_preference
+            || settings.features != inserted.features
+            || settings.disabled_features != inserted.disabled_features;
+        if replaced {
+            warn!(
+                "WgpuSettings inserted after RenderDocPlugin replaced the capture-friendly settings it inserted. \
+                 Insert them before adding the plugin, starting from capture_wgpu_settings()"
+            );
+        }
+    }
+
+    for conflict in conflicts(&settings) {
+        warn!("{}. Captures may be empty or fail to replay", conflict);
+    }
+}
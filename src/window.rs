@@ -0,0 +1,94 @@
+use std::ffi::c_void;
+use std::sync::Once;
+
+use bevy::ecs::event::ManualEventReader;
+use bevy::prelude::*;
+use bevy::render::renderer::RenderDevice;
+use bevy::window::{WindowFocused, WindowId};
+use raw_window_handle::RawWindowHandle;
+use renderdoc::{DevicePointer, WindowHandle};
+
+use crate::capture::wildcard_device;
+use crate::CaptureBackend;
+
+/// Warns, once per process, that windows can't be targeted on this backend.
+static WILDCARD_WARNING: Once = Once::new();
+
+/// Returns RenderDoc's device pointer for `device`, or a wildcard if the
+/// backend in use is not supported.
+pub(crate) fn device_pointer(device: &RenderDevice) -> DevicePointer {
+    #[cfg(any(windows, all(unix, not(target_os = "ios"), not(target_os = "macos"))))]
+    {
+        use ash::vk::Handle;
+
+        // RenderDoc identifies Vulkan devices by their instance's dispatch table.
+        let instance = unsafe {
+            device
+                .wgpu_device()
+                .as_hal::<wgpu_hal::api::Vulkan, _, _>(|device| {
+                    device.map(|device| device.shared_instance().raw_instance().handle().as_raw())
+                })
+        };
+        if let Some(instance) = instance {
+            let dispatch_table = unsafe { *(instance as *const *const c_void) };
+            return dispatch_table.into();
+        }
+    }
+
+    // RenderDoc ignores windows paired with a wildcard device.
+    let _ = device;
+    WILDCARD_WARNING.call_once(|| {
+        warn!(
+            "RenderDoc can only target windows with the Vulkan backend, so captures and focus \
+             changes use the window RenderDoc considers active"
+        )
+    });
+    wildcard_device()
+}
+
+/// Returns RenderDoc's handle for `window`, if it has been created.
+pub(crate) fn window_handle(window: &Window) -> Option<WindowHandle> {
+    let handle = match window.raw_handle()?.window_handle {
+        RawWindowHandle::Win32(handle) => handle.hwnd,
+        RawWindowHandle::Xlib(handle) => handle.window as *mut c_void,
+        RawWindowHandle::Xcb(handle) => handle.window as usize as *mut c_void,
+        RawWindowHandle::Wayland(handle) => handle.surface,
+        RawWindowHandle::AppKit(handle) => handle.ns_view,
+        _ => return None,
+    };
+    Some(handle as WindowHandle)
+}
+
+/// Returns the device pointer and window handle RenderDoc needs to capture `id`.
+pub(crate) fn capture_target(
+    windows: Option<&Windows>,
+    device: Option<&RenderDevice>,
+    id: WindowId,
+) -> Option<(DevicePointer, WindowHandle)> {
+    let window = window_handle(windows?.get(id)?)?;
+    Some((device_pointer(device?), window))
+}
+
+/// Makes the focused window RenderDoc's active window, so RenderDoc's own
+/// capture keys capture the window the user is looking at.
+pub(crate) fn activate_focused_window<B: CaptureBackend>(
+    focused: Option<Res<Events<WindowFocused>>>,
+    mut reader: Local<ManualEventReader<WindowFocused>>,
+    windows: Option<Res<Windows>>,
+    device: Option<Res<RenderDevice>>,
+    mut rd: NonSendMut<B>,
+) {
+    // Window events only exist once the window plugin has been added.
+    let focused = match focused {
+        Some(focused) => focused,
+        None => return,
+    };
+    let id = match reader.iter(&focused).rfind(|event| event.focused) {
+        Some(event) => event.id,
+        None => return,
+    };
+
+    if let Some((device, window)) = capture_target(windows.as_deref(), device.as_deref(), id) {
+        rd.set_active_window(device, window);
+    }
+}